@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+
+/// Vertical position within a row of the graph.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Line drawn within a row of the graph, from one lane to another.
+pub struct Segment {
+    pub(crate) from: (usize, Level),
+    pub(crate) to: (usize, Level),
+}
+
+/// Part of the graph drawn next to a single commit of the log.
+pub struct GraphRow {
+    /// Lane in which the commit itself sits.
+    pub(crate) column: usize,
+    /// Number of lanes the row spans.
+    pub(crate) width: usize,
+    pub(crate) segments: Vec<Segment>,
+}
+
+/// Assigns commits of a log to lanes, like `git log --graph` does.
+/// `oids` have to be ordered children first, and `parents` gives parents of each of them.
+/// Parents missing from `oids` are not drawn.
+pub fn layout<F>(oids: &[git2::Oid], mut parents: F) -> Vec<GraphRow>
+where
+    F: FnMut(git2::Oid) -> Vec<git2::Oid>,
+{
+    let present: HashSet<_> = oids.iter().copied().collect();
+    let mut lanes: Vec<Option<git2::Oid>> = Vec::new();
+    let mut rows = Vec::with_capacity(oids.len());
+    for &oid in oids {
+        let before = lanes.clone();
+        let column = match lanes.iter().position(|lane| *lane == Some(oid)) {
+            Some(column) => column,
+            None => allocate(&mut lanes),
+        };
+        let mut segments = Vec::new();
+        for (lane, expected) in before.iter().enumerate() {
+            match expected {
+                Some(expected) if *expected == oid => {
+                    segments.push(Segment {
+                        from: (lane, Level::Top),
+                        to: (column, Level::Middle),
+                    });
+                    lanes[lane] = None;
+                }
+                Some(_) => segments.push(Segment {
+                    from: (lane, Level::Top),
+                    to: (lane, Level::Bottom),
+                }),
+                None => {}
+            }
+        }
+        lanes[column] = None;
+        for (index, parent) in parents(oid)
+            .into_iter()
+            .filter(|parent| present.contains(parent))
+            .enumerate()
+        {
+            let lane = match lanes.iter().position(|lane| *lane == Some(parent)) {
+                Some(lane) => lane,
+                None if index == 0 => {
+                    lanes[column] = Some(parent);
+                    column
+                }
+                None => {
+                    let lane = allocate(&mut lanes);
+                    lanes[lane] = Some(parent);
+                    lane
+                }
+            };
+            segments.push(Segment {
+                from: (column, Level::Middle),
+                to: (lane, Level::Bottom),
+            });
+        }
+        while lanes.last() == Some(&None) {
+            lanes.pop();
+        }
+        rows.push(GraphRow {
+            column,
+            width: before.len().max(lanes.len()).max(column + 1),
+            segments,
+        });
+    }
+    rows
+}
+
+/// Finds a free lane, adding one if all are taken.
+fn allocate(lanes: &mut Vec<Option<git2::Oid>>) -> usize {
+    match lanes.iter().position(Option::is_none) {
+        Some(lane) => lane,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Point = (usize, char);
+    type Row = (usize, usize, Vec<(Point, Point)>);
+
+    fn oid(n: u8) -> git2::Oid {
+        git2::Oid::from_bytes(&[n; 20]).unwrap()
+    }
+
+    /// Lays out commits given as `(commit, parents)`, children first, and describes each row
+    /// as its column, width and segments, with levels written as `t`, `m` and `b`.
+    fn draw(history: &[(u8, &[u8])]) -> Vec<Row> {
+        let oids: Vec<_> = history.iter().map(|&(n, _)| oid(n)).collect();
+        let parents = |commit: git2::Oid| {
+            history
+                .iter()
+                .find(|&&(n, _)| oid(n) == commit)
+                .map(|(_, parents)| parents.iter().map(|&n| oid(n)).collect())
+                .unwrap_or_default()
+        };
+        let point = |(lane, level): (usize, Level)| {
+            let level = match level {
+                Level::Top => 't',
+                Level::Middle => 'm',
+                Level::Bottom => 'b',
+            };
+            (lane, level)
+        };
+        layout(&oids, parents)
+            .into_iter()
+            .map(|row| {
+                let segments = row
+                    .segments
+                    .iter()
+                    .map(|segment| (point(segment.from), point(segment.to)))
+                    .collect();
+                (row.column, row.width, segments)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn linear_history_stays_in_one_lane() {
+        assert_eq!(
+            draw(&[(3, &[2]), (2, &[1]), (1, &[])]),
+            [
+                (0, 1, vec![((0, 'm'), (0, 'b'))]),
+                (0, 1, vec![((0, 't'), (0, 'm')), ((0, 'm'), (0, 'b'))]),
+                (0, 1, vec![((0, 't'), (0, 'm'))]),
+            ]
+        );
+    }
+
+    #[test]
+    fn fork_and_merge_open_and_close_a_lane() {
+        assert_eq!(
+            draw(&[(4, &[2, 3]), (3, &[1]), (2, &[1]), (1, &[])]),
+            [
+                (0, 2, vec![((0, 'm'), (0, 'b')), ((0, 'm'), (1, 'b'))]),
+                (
+                    1,
+                    2,
+                    vec![
+                        ((0, 't'), (0, 'b')),
+                        ((1, 't'), (1, 'm')),
+                        ((1, 'm'), (1, 'b')),
+                    ]
+                ),
+                (
+                    0,
+                    2,
+                    vec![
+                        ((0, 't'), (0, 'm')),
+                        ((1, 't'), (1, 'b')),
+                        ((0, 'm'), (1, 'b')),
+                    ]
+                ),
+                (1, 2, vec![((1, 't'), (1, 'm'))]),
+            ]
+        );
+    }
+
+    #[test]
+    fn octopus_merge_opens_a_lane_per_parent() {
+        assert_eq!(
+            draw(&[(5, &[2, 3, 4]), (2, &[1]), (3, &[1]), (4, &[1]), (1, &[])]),
+            [
+                (
+                    0,
+                    3,
+                    vec![
+                        ((0, 'm'), (0, 'b')),
+                        ((0, 'm'), (1, 'b')),
+                        ((0, 'm'), (2, 'b')),
+                    ]
+                ),
+                (
+                    0,
+                    3,
+                    vec![
+                        ((0, 't'), (0, 'm')),
+                        ((1, 't'), (1, 'b')),
+                        ((2, 't'), (2, 'b')),
+                        ((0, 'm'), (0, 'b')),
+                    ]
+                ),
+                (
+                    1,
+                    3,
+                    vec![
+                        ((0, 't'), (0, 'b')),
+                        ((1, 't'), (1, 'm')),
+                        ((2, 't'), (2, 'b')),
+                        ((1, 'm'), (0, 'b')),
+                    ]
+                ),
+                (
+                    2,
+                    3,
+                    vec![
+                        ((0, 't'), (0, 'b')),
+                        ((2, 't'), (2, 'm')),
+                        ((2, 'm'), (0, 'b')),
+                    ]
+                ),
+                (0, 1, vec![((0, 't'), (0, 'm'))]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parents_outside_the_log_are_not_drawn() {
+        assert_eq!(
+            draw(&[(3, &[2, 9]), (2, &[8])]),
+            [
+                (0, 1, vec![((0, 'm'), (0, 'b'))]),
+                (0, 1, vec![((0, 't'), (0, 'm'))]),
+            ]
+        );
+    }
+}
@@ -1,31 +1,115 @@
 use anyhow::Result;
 use argh::FromArgs;
 use std::path::PathBuf;
-use thiserror::Error;
 
+pub(crate) mod graph;
+pub(crate) mod media;
 pub(crate) mod repository;
 pub(crate) mod templates;
 
 /// Generate a static website presenting nicely contents of a git repository.
 #[derive(FromArgs)]
 struct Args {
-    /// directory with git repository to process
+    /// directory with git repository to process, or any directory inside its working tree
     #[argh(positional)]
     source: PathBuf,
 
     /// directory to write html files into
     #[argh(positional)]
     destination: PathBuf,
+
+    /// url prefix under which rustagit pages of submodule repositories live, relative to the destination unless absolute
+    #[argh(option)]
+    submodule_pages: Option<String>,
+
+    /// write raw copies of files into a separate raw/ directory instead of next to their pages
+    #[argh(switch)]
+    raw_dir: bool,
+
+    /// branch to present when HEAD is unborn or points to a missing branch, main or master by default; other branches get compared with it, or with HEAD if not given
+    #[argh(option)]
+    default_branch: Option<String>,
+
+    /// maximum number of commits to present
+    #[argh(option, short = 'l')]
+    max_commits: Option<usize>,
+
+    /// present only commits newer than a date (like 2020-01-31) or an age (like 6months)
+    #[argh(option, from_str_fn(parse_date))]
+    since: Option<i64>,
+
+    /// present only commits older than a date (like 2020-01-31) or an age (like 6months)
+    #[argh(option, from_str_fn(parse_date))]
+    until: Option<i64>,
+
+    /// follow only the first parent of merge commits
+    #[argh(switch)]
+    first_parent: bool,
+
+    /// order of commits in the log: default, time or topo; unless reversed, parents always follow their children
+    #[argh(option, default = "repository::LogOrder::Default")]
+    log_order: repository::LogOrder,
+
+    /// present oldest commits first
+    #[argh(switch)]
+    reverse: bool,
+
+    /// number of commits per page of the commit log, all on one page by default
+    #[argh(option)]
+    log_page_size: Option<usize>,
+
+    /// branch to generate a separate commit log for, may be given multiple times
+    #[argh(option)]
+    log_branch: Vec<String>,
+
+    /// order of tags in listings: semver, date or name
+    #[argh(option, default = "repository::TagOrder::Semver")]
+    tag_order: repository::TagOrder,
+
+    /// pair of revisions like v1.0..main to generate a compare page for, may be given multiple times
+    #[argh(option, from_str_fn(parse_revision_pair))]
+    compare: Vec<(String, String)>,
+
+    /// age of the last commit after which a branch counts as stale, like 6months, 3months by default
+    #[argh(option, default = "\"3months\".parse().unwrap()")]
+    stale_after: humantime::Duration,
+
+    /// notes ref to show on commit pages besides refs/notes/commits, like ci or refs/notes/review, may be given multiple times
+    #[argh(option)]
+    notes_ref: Vec<String>,
+}
+
+/// Parses a date, a date with time, or an age relative to now into a unix timestamp.
+fn parse_date(value: &str) -> Result<i64, String> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms(0, 0, 0).timestamp());
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp());
+    }
+    match humantime::parse_duration(value) {
+        Ok(age) => Ok(chrono::Utc::now().timestamp() - age.as_secs() as i64),
+        Err(e) => Err(format!("{} is neither a date nor an age: {}", value, e)),
+    }
 }
 
-#[derive(Error, Debug)]
-#[error("invalid utf sequence")]
-pub struct InvalidUtf;
+/// Splits a revision range like v1.0..v1.1 into its two ends.
+fn parse_revision_pair(value: &str) -> Result<(String, String), String> {
+    match value.split_once("..") {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_string(), to.to_string()))
+        }
+        _ => Err(format!(
+            "{} is not a pair of revisions like v1.0..v1.1",
+            value
+        )),
+    }
+}
 
 fn main() -> Result<()> {
     better_panic::install();
     let args: Args = argh::from_env();
-    let mut repository = repository::Repository::open(args.source)?;
+    let mut repository = repository::Repository::open(args.source, args.default_branch)?;
     repository.prefetch_name();
     repository.prefetch_description();
     repository.prefetch_url();
@@ -40,6 +124,22 @@ fn main() -> Result<()> {
         url,
         syntax_set,
         theme,
+        submodule_pages: args.submodule_pages,
+        raw_dir: args.raw_dir,
+        log_options: repository::LogOptions {
+            max_count: args.max_commits,
+            since: args.since,
+            until: args.until,
+            first_parent: args.first_parent,
+            order: args.log_order,
+            reverse: args.reverse,
+        },
+        log_page_size: args.log_page_size,
+        log_branches: args.log_branch,
+        tag_order: args.tag_order,
+        compare_pairs: args.compare,
+        stale_after: args.stale_after.into(),
+        notes_refs: args.notes_ref,
     };
 
     templator.generate()?;
@@ -0,0 +1,175 @@
+/// Kind of media that browsers can embed directly.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Audio,
+    Video,
+}
+
+/// Media file recognized by its magic bytes.
+pub struct Media {
+    pub(crate) kind: MediaKind,
+    pub(crate) mime: &'static str,
+}
+
+impl Media {
+    /// Recognizes common image, audio and video formats by looking at the start of the content.
+    pub fn detect(content: &[u8]) -> Option<Media> {
+        let media = |kind, mime| Some(Media { kind, mime });
+        let riff_kind = content.get(8..12);
+        let ftyp_brand = content.get(8..12);
+        match content {
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => {
+                media(MediaKind::Image, "image/png")
+            }
+            [0xff, 0xd8, 0xff, ..] => media(MediaKind::Image, "image/jpeg"),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => media(MediaKind::Image, "image/gif"),
+            [b'B', b'M', ..] if is_bmp(content) => media(MediaKind::Image, "image/bmp"),
+            [0, 0, 1, 0, ..] if is_ico(content) => media(MediaKind::Image, "image/x-icon"),
+            [b'R', b'I', b'F', b'F', ..] if riff_kind == Some(b"WEBP") => {
+                media(MediaKind::Image, "image/webp")
+            }
+            [b'R', b'I', b'F', b'F', ..] if riff_kind == Some(b"WAVE") => {
+                media(MediaKind::Audio, "audio/wav")
+            }
+            [b'I', b'D', b'3', ..] | [0xff, 0xfb | 0xf3 | 0xf2, ..] => {
+                media(MediaKind::Audio, "audio/mpeg")
+            }
+            [b'f', b'L', b'a', b'C', ..] => media(MediaKind::Audio, "audio/flac"),
+            [b'O', b'g', b'g', b'S', ..] => media(MediaKind::Audio, "audio/ogg"),
+            [0x1a, 0x45, 0xdf, 0xa3, ..] => media(MediaKind::Video, "video/webm"),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..] => match ftyp_brand {
+                Some(b"avif" | b"avis") => media(MediaKind::Image, "image/avif"),
+                Some(b"heic" | b"heix" | b"heim" | b"heis") => {
+                    media(MediaKind::Image, "image/heic")
+                }
+                Some(b"mif1" | b"msf1") => media(MediaKind::Image, "image/heif"),
+                Some(b"M4A ") => media(MediaKind::Audio, "audio/mp4"),
+                Some(b"qt  ") => media(MediaKind::Video, "video/quicktime"),
+                _ => media(MediaKind::Video, "video/mp4"),
+            },
+            _ => None,
+        }
+    }
+}
+
+fn u32_le(content: &[u8], offset: usize) -> Option<u32> {
+    let bytes = content.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Checks the BMP file header: reserved fields are zero, the pixel data starts within the file,
+/// and the DIB header has one of the known sizes.
+fn is_bmp(content: &[u8]) -> bool {
+    let (reserved, offset, dib_size) =
+        match (u32_le(content, 6), u32_le(content, 10), u32_le(content, 14)) {
+            (Some(reserved), Some(offset), Some(dib_size)) => (reserved, offset as usize, dib_size),
+            _ => return false,
+        };
+    reserved == 0
+        && offset >= 14 + dib_size as usize
+        && offset <= content.len()
+        && matches!(dib_size, 12 | 40 | 52 | 56 | 64 | 108 | 124)
+}
+
+/// Checks the ICO header: at least one image, with all directory entries present.
+fn is_ico(content: &[u8]) -> bool {
+    let count = match content.get(4..6) {
+        Some(&[low, high]) => u16::from_le_bytes([low, high]) as usize,
+        _ => return false,
+    };
+    count > 0
+        && content.len() >= 6 + 16 * count
+        && (0..count).all(|index| content[6 + 16 * index + 3] == 0)
+}
+
+/// Formats a size in bytes using binary prefixes.
+pub fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mime(content: &[u8]) -> Option<&'static str> {
+        Media::detect(content).map(|media| media.mime)
+    }
+
+    fn bmp() -> Vec<u8> {
+        let mut content = b"BM".to_vec();
+        content.extend_from_slice(&58u32.to_le_bytes());
+        content.extend_from_slice(&0u32.to_le_bytes());
+        content.extend_from_slice(&54u32.to_le_bytes());
+        content.extend_from_slice(&40u32.to_le_bytes());
+        content.resize(58, 0);
+        content
+    }
+
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut content = vec![0, 0, 0, 0x18];
+        content.extend_from_slice(b"ftyp");
+        content.extend_from_slice(brand);
+        content.resize(0x18, 0);
+        content
+    }
+
+    #[test]
+    fn common_images_are_recognized() {
+        assert_eq!(mime(b"\x89PNG\r\n\x1a\n\0\0"), Some("image/png"));
+        assert_eq!(mime(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(mime(b"GIF89a"), Some("image/gif"));
+        assert_eq!(mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(mime(&bmp()), Some("image/bmp"));
+    }
+
+    #[test]
+    fn text_starting_like_bmp_is_not_an_image() {
+        assert_eq!(mime(b"BMW drivers are listed below.\n"), None);
+        assert_eq!(mime(b"BM"), None);
+    }
+
+    #[test]
+    fn ico_needs_its_image_directory() {
+        let mut ico = vec![0, 0, 1, 0, 1, 0];
+        ico.extend_from_slice(&[16, 16, 0, 0, 1, 0, 32, 0, 0, 0, 0, 0, 22, 0, 0, 0]);
+        assert_eq!(mime(&ico), Some("image/x-icon"));
+        assert_eq!(mime(&[0, 0, 1, 0, 0, 0]), None);
+        assert_eq!(mime(&[0, 0, 1, 0, 1, 0, 16, 16]), None);
+    }
+
+    #[test]
+    fn ftyp_brands_decide_the_kind() {
+        assert_eq!(mime(&ftyp(b"avif")), Some("image/avif"));
+        assert_eq!(mime(&ftyp(b"heic")), Some("image/heic"));
+        assert_eq!(mime(&ftyp(b"mif1")), Some("image/heif"));
+        assert_eq!(mime(&ftyp(b"M4A ")), Some("audio/mp4"));
+        assert_eq!(mime(&ftyp(b"qt  ")), Some("video/quicktime"));
+        assert_eq!(mime(&ftyp(b"isom")), Some("video/mp4"));
+        assert!(Media::detect(&ftyp(b"avif")).is_some_and(|media| media.kind == MediaKind::Image));
+    }
+
+    #[test]
+    fn audio_is_recognized() {
+        assert_eq!(mime(b"RIFF\0\0\0\0WAVEfmt "), Some("audio/wav"));
+        assert_eq!(mime(b"ID3\x04"), Some("audio/mpeg"));
+        assert_eq!(mime(b"fLaC"), Some("audio/flac"));
+        assert_eq!(mime(b"OggS"), Some("audio/ogg"));
+    }
+
+    #[test]
+    fn plain_text_is_not_media() {
+        assert_eq!(mime(b"fn main() {}\n"), None);
+        assert_eq!(mime(b""), None);
+    }
+}
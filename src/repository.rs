@@ -1,18 +1,492 @@
 use anyhow::Result;
 use cached_property::{cached_property, cached_property_struct};
 use fs_err as fs;
+use std::cmp::Reverse;
+use std::os::unix::ffi::OsStrExt;
 
 pub struct CommitInfo<'a> {
     pub(crate) commit: git2::Commit<'a>,
     pub(crate) diff: git2::Diff<'a>,
+    /// Whether history of a shallow clone is cut off at this commit,
+    /// in which case `diff` is empty rather than against a missing parent.
+    pub(crate) shallow: bool,
 }
 
 impl CommitInfo<'_> {
     pub fn time(&self) -> chrono::DateTime<chrono::FixedOffset> {
-        use chrono::TimeZone;
-        let commit_time = self.commit.time();
-        let offset = chrono::FixedOffset::east(commit_time.offset_minutes() * 60);
-        offset.timestamp(commit_time.seconds(), 0)
+        commit_time(&self.commit)
+    }
+}
+
+/// Time of a commit in the timezone of its committer.
+pub fn commit_time(commit: &git2::Commit) -> chrono::DateTime<chrono::FixedOffset> {
+    signature_time(&commit.time())
+}
+
+/// Converts time recorded by git into a timestamp with the recorded offset.
+pub fn signature_time(time: &git2::Time) -> chrono::DateTime<chrono::FixedOffset> {
+    use chrono::TimeZone;
+    let offset = chrono::FixedOffset::east(time.offset_minutes() * 60);
+    offset.timestamp(time.seconds(), 0)
+}
+
+/// Turns errors caused by objects missing from a partial clone into None.
+pub fn unless_missing<T>(result: Result<T, git2::Error>) -> Result<Option<T>, git2::Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Git filemode of tree entries that are symbolic links.
+pub const FILEMODE_SYMLINK: i32 = 0o120000;
+
+/// Where a symbolic link stored in a tree points to.
+pub enum SymlinkTarget {
+    /// Path of an existing entry in the same tree, relative to its root.
+    Inside(std::path::PathBuf),
+    /// Path inside the tree, but nothing exists there.
+    Broken,
+    /// Absolute path or one escaping the root of the tree.
+    OutOfTree,
+}
+
+/// Submodule as declared in a `.gitmodules` file.
+pub struct Submodule {
+    pub(crate) name: String,
+    pub(crate) path: String,
+    pub(crate) url: Option<String>,
+}
+
+impl Submodule {
+    /// Guesses the name of the repository behind the submodule from its URL.
+    pub fn repository_name(&self) -> &str {
+        self.url
+            .as_deref()
+            .map(|url| url.trim_end_matches('/'))
+            .and_then(|url| url.rsplit(['/', ':']).next())
+            .map(|name| name.strip_suffix(".git").unwrap_or(name))
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.name)
+    }
+}
+
+/// Parses the subset of git config syntax used by `.gitmodules` files.
+fn parse_gitmodules(content: &str) -> Vec<Submodule> {
+    let mut submodules: Vec<Submodule> = Vec::new();
+    let mut in_submodule = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[') {
+            let section = section.trim_end_matches(']').trim();
+            in_submodule = false;
+            if let Some(name) = section.strip_prefix("submodule") {
+                let name = name.trim().trim_matches('"');
+                in_submodule = true;
+                submodules.push(Submodule {
+                    name: name.to_string(),
+                    path: name.to_string(),
+                    url: None,
+                });
+            }
+            continue;
+        }
+        if !in_submodule {
+            continue;
+        }
+        if let (Some((key, value)), Some(submodule)) = (line.split_once('='), submodules.last_mut())
+        {
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "path" => submodule.path = value,
+                "url" => submodule.url = Some(value),
+                _ => {}
+            }
+        }
+    }
+    submodules
+}
+
+/// Matches `text` against a gitignore-style glob supporting `*`, `**` and `?`.
+/// `**/` stands for any number of whole leading directories, a trailing `**` for anything,
+/// and other runs of asterisks behave like a single one.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => (0..=text.len())
+            .filter(|&i| i == 0 || text[i - 1] == b'/')
+            .any(|i| glob_match(rest, &text[i..])),
+        [b'*', b'*'] => true,
+        [b'*', b'*', ..] => glob_match(&pattern[1..], text),
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(rest, &text[i..])),
+        [b'?', rest @ ..] => matches!(text, [c, tail @ ..] if *c != b'/' && glob_match(rest, tail)),
+        [p, rest @ ..] => matches!(text, [c, tail @ ..] if c == p && glob_match(rest, tail)),
+    }
+}
+
+/// Reads a tag name like v1.2, 1.2.3-rc.1, release-2.0 or 2021.01.05 as a semantic version,
+/// filling in missing minor and patch numbers and dropping leading zeros.
+fn parse_version(name: &str) -> Option<semver::Version> {
+    let version = &name[name.find(|c: char| c.is_ascii_digit())?..];
+    let (core, suffix) = version.split_at(version.find(['-', '+']).unwrap_or(version.len()));
+    let mut components: Vec<_> = core
+        .split('.')
+        .map(|component| match component.trim_start_matches('0') {
+            "" if !component.is_empty() => "0",
+            trimmed => trimmed,
+        })
+        .collect();
+    if components.len() > 3 {
+        return None;
+    }
+    components.resize(3, "0");
+    semver::Version::parse(&format!("{}{}", components.join("."), suffix)).ok()
+}
+
+/// Looks up the value of attribute `name` for `path` in the lines of a `.gitattributes` file
+/// located in `dir`, returning the value set by the last matching line.
+fn lookup_gitattributes(
+    content: &str,
+    dir: &std::path::Path,
+    path: &std::path::Path,
+    name: &str,
+) -> Option<Option<String>> {
+    let relative = path.strip_prefix(dir).ok()?.as_os_str().as_bytes();
+    let basename = path.file_name()?.as_bytes();
+    let mut found = None;
+    for line in content.lines() {
+        let mut tokens = line.split_whitespace();
+        let pattern = match tokens.next() {
+            Some(pattern) if !pattern.starts_with('#') => pattern,
+            _ => continue,
+        };
+        let matched = if pattern.contains('/') {
+            glob_match(pattern.trim_start_matches('/').as_bytes(), relative)
+        } else {
+            glob_match(pattern.as_bytes(), basename)
+        };
+        if !matched {
+            continue;
+        }
+        for attribute in tokens {
+            match attribute.split_once('=') {
+                Some((key, value)) if key == name => found = Some(Some(value.to_string())),
+                None if attribute.trim_start_matches(&['-', '!'][..]) == name => found = Some(None),
+                _ => {}
+            }
+        }
+    }
+    found
+}
+
+/// Tag together with what it points to.
+pub struct TagInfo<'a> {
+    pub(crate) name: String,
+    /// Tag object of annotated tags, None for lightweight ones.
+    pub(crate) tag: Option<git2::Tag<'a>>,
+    /// Object the tag points to, with any chain of tags peeled off.
+    pub(crate) target: git2::Object<'a>,
+}
+
+impl TagInfo<'_> {
+    /// Unix timestamp of tagging, or of the tagged commit for lightweight tags.
+    pub fn time(&self) -> Option<i64> {
+        let tagger = self.tag.as_ref().and_then(|tag| tag.tagger());
+        match tagger {
+            Some(tagger) => Some(tagger.when().seconds()),
+            None => self
+                .target
+                .as_commit()
+                .map(|commit| commit.time().seconds()),
+        }
+    }
+}
+
+/// Order in which tags get listed, newest first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TagOrder {
+    /// By semantic version, pre-releases before final ones, then by date.
+    /// Tags that are not versions come after all that are.
+    #[default]
+    Semver,
+    /// By the date of tagging or of the tagged commit.
+    Date,
+    /// By name, in reverse lexical order.
+    Name,
+}
+
+impl std::str::FromStr for TagOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "semver" => Ok(TagOrder::Semver),
+            "date" => Ok(TagOrder::Date),
+            "name" => Ok(TagOrder::Name),
+            _ => Err(format!(
+                "unknown tag order {}, expected semver, date or name",
+                s
+            )),
+        }
+    }
+}
+
+/// Tag of a commit, together with the commits it brings since the previous release.
+pub struct Release<'a> {
+    pub(crate) tag: TagInfo<'a>,
+    pub(crate) commit: git2::Commit<'a>,
+    /// Name and commit of the previous release, None for the first one.
+    pub(crate) previous: Option<(String, git2::Commit<'a>)>,
+    /// Commits reachable from this release but not from the previous one, newest first.
+    pub(crate) commits: CommitLog,
+}
+
+/// Commits between two revisions, like `git log from..to`.
+pub struct Comparison<'a> {
+    pub(crate) from: String,
+    pub(crate) to: String,
+    pub(crate) from_commit: git2::Commit<'a>,
+    pub(crate) to_commit: git2::Commit<'a>,
+    /// Commits reachable from `to` but not from `from`, newest first.
+    pub(crate) commits: CommitLog,
+}
+
+/// How the history of a branch relates to the default branch.
+pub struct Divergence {
+    /// Number of commits on the branch that are not on the default branch.
+    pub(crate) ahead: usize,
+    /// Number of commits on the default branch that are not on the branch.
+    pub(crate) behind: usize,
+    pub(crate) merge_base: Option<git2::Oid>,
+}
+
+/// Branches and tags related to each commit that gets a page.
+#[derive(Default)]
+pub struct Containment {
+    branch_names: Vec<String>,
+    tag_names: Vec<String>,
+    commits: std::collections::HashMap<git2::Oid, CommitRefs>,
+}
+
+#[derive(Default)]
+struct CommitRefs {
+    /// Indexes of branches including the commit.
+    branches: Vec<usize>,
+    /// Closest tag including the commit, like `git describe --contains` finds.
+    contained_in: Option<usize>,
+    /// Closest tag included by the commit, like `git describe` finds,
+    /// with the number of commits on the shortest way from it.
+    described_by: Option<(usize, usize)>,
+}
+
+impl Containment {
+    pub fn branches_containing(&self, oid: git2::Oid) -> impl Iterator<Item = &str> {
+        let branches = self
+            .commits
+            .get(&oid)
+            .map_or(&[][..], |refs| &refs.branches);
+        branches
+            .iter()
+            .map(move |&index| self.branch_names[index].as_str())
+    }
+
+    pub fn first_tag_containing(&self, oid: git2::Oid) -> Option<&str> {
+        let index = self.commits.get(&oid)?.contained_in?;
+        Some(&self.tag_names[index])
+    }
+
+    /// Finds the closest preceding tag and the number of commits made since it.
+    pub fn describe(&self, oid: git2::Oid) -> Option<(&str, usize)> {
+        let (index, distance) = self.commits.get(&oid)?.described_by?;
+        Some((&self.tag_names[index], distance))
+    }
+}
+
+/// What reaches a commit from its descendants, while walking history children first.
+#[derive(Clone)]
+struct Reach {
+    /// Bit set of branches whose tips are descendants.
+    branches: Vec<u64>,
+    /// Distance to and index of the closest tagged descendant.
+    tag: Option<(usize, usize)>,
+}
+
+impl Reach {
+    fn new(branch_count: usize) -> Self {
+        Reach {
+            branches: vec![0; branch_count.div_ceil(64)],
+            tag: None,
+        }
+    }
+
+    fn merge(&mut self, other: &Reach) {
+        for (bits, other_bits) in self.branches.iter_mut().zip(&other.branches) {
+            *bits |= other_bits;
+        }
+        self.tag = match (self.tag, other.tag) {
+            (Some(tag), Some(other_tag)) => Some(tag.min(other_tag)),
+            (tag, other_tag) => tag.or(other_tag),
+        };
+    }
+}
+
+/// Limits on which commits get presented.
+#[derive(Default)]
+pub struct LogOptions {
+    pub(crate) max_count: Option<usize>,
+    /// Unix timestamp of the oldest commit to include.
+    pub(crate) since: Option<i64>,
+    /// Unix timestamp of the newest commit to include.
+    pub(crate) until: Option<i64>,
+    pub(crate) first_parent: bool,
+    pub(crate) order: LogOrder,
+    /// Present the oldest commits first; limits still pick the newest ones.
+    pub(crate) reverse: bool,
+}
+
+/// Order in which commits get presented. Unless the log is reversed, parents still never
+/// come before their children, so that the log can be drawn as a graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogOrder {
+    /// Newest first, as libgit2 walks history by default.
+    #[default]
+    Default,
+    /// Strictly by commit time, newest first.
+    Time,
+    /// No parent before any of its children.
+    Topological,
+}
+
+impl std::str::FromStr for LogOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(LogOrder::Default),
+            "time" => Ok(LogOrder::Time),
+            "topo" | "topological" => Ok(LogOrder::Topological),
+            _ => Err(format!(
+                "unknown log order {}, expected default, time or topo",
+                s
+            )),
+        }
+    }
+}
+
+/// Commits selected for presentation, newest first.
+#[derive(Clone, Default)]
+pub struct CommitLog {
+    pub(crate) oids: Vec<git2::Oid>,
+    /// Whether older commits were left out because of `LogOptions`.
+    pub(crate) truncated: bool,
+}
+
+/// Walks history newest first like a revwalk does, but stops at boundaries of a shallow clone
+/// instead of failing on parents that were never fetched.
+struct ShallowWalk<'r> {
+    repository: &'r Repository,
+    queue: std::collections::BinaryHeap<(i64, git2::Oid)>,
+    seen: std::collections::HashSet<git2::Oid>,
+    first_parent: bool,
+}
+
+impl<'r> ShallowWalk<'r> {
+    fn new<I: Iterator<Item = git2::Oid>>(
+        repository: &'r Repository,
+        tips: I,
+        first_parent: bool,
+    ) -> Self {
+        let mut walk = ShallowWalk {
+            repository,
+            queue: Default::default(),
+            seen: Default::default(),
+            first_parent,
+        };
+        for tip in tips {
+            walk.enqueue(tip);
+        }
+        walk
+    }
+
+    fn enqueue(&mut self, oid: git2::Oid) {
+        if self.seen.insert(oid) {
+            let time = self
+                .repository
+                .inner
+                .find_commit(oid)
+                .map(|commit| commit.time().seconds())
+                .unwrap_or_default();
+            self.queue.push((time, oid));
+        }
+    }
+}
+
+impl Iterator for ShallowWalk<'_> {
+    type Item = Result<git2::Oid, git2::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, oid) = self.queue.pop()?;
+        if !self.repository.shallow.contains(&oid) {
+            let commit = match self.repository.inner.find_commit(oid) {
+                Ok(commit) => commit,
+                Err(e) => return Some(Err(e)),
+            };
+            let parent_count = if self.first_parent { 1 } else { usize::MAX };
+            for parent in commit.parent_ids().take(parent_count) {
+                self.enqueue(parent);
+            }
+        }
+        Some(Ok(oid))
+    }
+}
+
+impl ShallowWalk<'_> {
+    /// Orders the whole history so that no parent comes before any of its children,
+    /// like `git2::Sort::TOPOLOGICAL` does for complete histories.
+    fn topological(self) -> Result<Vec<git2::Oid>, git2::Error> {
+        let repository = self.repository;
+        let first_parent = self.first_parent;
+        let mut commits = std::collections::HashMap::new();
+        for oid in self {
+            let oid = oid?;
+            commits.insert(oid, repository.inner.find_commit(oid)?);
+        }
+        let parents_of = |commit: &git2::Commit| -> Vec<git2::Oid> {
+            if repository.shallow.contains(&commit.id()) {
+                return vec![];
+            }
+            let parent_count = if first_parent { 1 } else { usize::MAX };
+            commit.parent_ids().take(parent_count).collect()
+        };
+        let mut children = std::collections::HashMap::<git2::Oid, usize>::new();
+        for commit in commits.values() {
+            for parent in parents_of(commit) {
+                *children.entry(parent).or_default() += 1;
+            }
+        }
+        let mut ready: std::collections::BinaryHeap<_> = commits
+            .values()
+            .filter(|commit| !children.contains_key(&commit.id()))
+            .map(|commit| (commit.time().seconds(), commit.id()))
+            .collect();
+        let mut sorted = Vec::with_capacity(commits.len());
+        while let Some((_, oid)) = ready.pop() {
+            sorted.push(oid);
+            for parent in parents_of(&commits[&oid]) {
+                let remaining = children.get_mut(&parent).unwrap();
+                *remaining -= 1;
+                if *remaining == 0 {
+                    ready.push((commits[&parent].time().seconds(), parent));
+                }
+            }
+        }
+        Ok(sorted)
     }
 }
 
@@ -20,24 +494,72 @@ impl CommitInfo<'_> {
 pub struct Repository {
     pub(crate) inner: git2::Repository,
     pub(crate) path: std::path::PathBuf,
+    pub(crate) default_branch: Option<String>,
+    /// Commits at which history of a shallow clone is cut off.
+    pub(crate) shallow: std::collections::HashSet<git2::Oid>,
 }
 
 impl Repository {
-    pub fn open<S: AsRef<std::path::Path>>(path: S) -> Result<Repository> {
+    /// Opens the repository containing `path`, searching parent directories like git does.
+    /// Bare repositories, `.git` files and linked worktrees are supported.
+    /// When `GIT_DIR` is set, it takes precedence just as it does for git itself.
+    pub fn open<S: AsRef<std::path::Path>>(
+        path: S,
+        default_branch: Option<String>,
+    ) -> Result<Repository> {
+        let inner = if std::env::var_os("GIT_DIR").is_some() {
+            git2::Repository::open_from_env()?
+        } else {
+            git2::Repository::discover(path.as_ref())?
+        };
+        let path = fs_err::canonicalize(Self::common_dir(&inner))?;
+        let shallow = fs::read_to_string(path.join("shallow"))
+            .map(|content| {
+                content
+                    .lines()
+                    .filter_map(|line| git2::Oid::from_str(line.trim()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
         Ok(Repository {
-            inner: git2::Repository::open(path.as_ref())?,
-            path: fs_err::canonicalize(path.as_ref())?,
+            inner,
+            path,
+            default_branch,
+            shallow,
             cached_properties: Default::default(),
         })
     }
 
+    /// Finds the directory shared by all worktrees of a repository,
+    /// which is where its description and url live.
+    fn common_dir(inner: &git2::Repository) -> std::path::PathBuf {
+        if inner.is_worktree() {
+            if let Ok(common) = fs::read_to_string(inner.path().join("commondir")) {
+                return inner.path().join(common.trim());
+            }
+        }
+        inner.path().to_path_buf()
+    }
+
     pub fn gitdir(&self) -> &std::path::Path {
-        self.inner.path()
+        &self.path
     }
 
+    /// Name of the repository: its directory name without the `.git` suffix,
+    /// or the name of the directory containing `.git`.
     #[cached_property]
     pub fn name(&self) -> String {
-        self.path.file_name().unwrap().to_string_lossy().to_string()
+        let file_name = self.path.file_name().unwrap_or_default();
+        let name = if file_name == ".git" {
+            self.path
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .unwrap_or(file_name)
+        } else {
+            file_name
+        };
+        let name = name.to_string_lossy();
+        name.strip_suffix(".git").unwrap_or(&name).to_string()
     }
 
     /// Reads a text file from a given file in .git.
@@ -60,21 +582,770 @@ impl Repository {
         self.read_gitdir_or_blank("url")
     }
 
-    pub fn commit_log(&self) -> Result<impl Iterator<Item = Result<CommitInfo<'_>>>> {
-        let mut log_walk = self.inner.revwalk()?;
-        log_walk.push_head()?;
-        Ok(log_walk.map(move |oid_result| -> Result<_> {
+    /// Finds the commit to present: the one HEAD points to, even when detached,
+    /// or the tip of the default branch when HEAD is unborn or points to a missing branch.
+    /// Returns None for repositories without commits.
+    pub fn head_commit(&self) -> Result<Option<git2::Commit<'_>>> {
+        if let Ok(head) = self.inner.head() {
+            return Ok(Some(head.peel_to_commit()?));
+        }
+        let candidates = match &self.default_branch {
+            Some(branch) => vec![branch.as_str()],
+            None => vec!["main", "master"],
+        };
+        for candidate in candidates {
+            if let Ok(branch) = self.inner.find_branch(candidate, git2::BranchType::Local) {
+                return Ok(Some(branch.get().peel_to_commit()?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds the branch other branches get compared with: the one given with `--default-branch`,
+    /// or HEAD if there is no such branch. Returns its name together with its tip.
+    pub fn default_branch_tip(&self) -> Result<Option<(String, git2::Commit<'_>)>> {
+        if let Some(branch) = &self.default_branch {
+            if let Ok(found) = self.inner.find_branch(branch, git2::BranchType::Local) {
+                return Ok(Some((branch.clone(), found.get().peel_to_commit()?)));
+            }
+        }
+        Ok(self.head_commit()?.map(|head| ("HEAD".to_string(), head)))
+    }
+
+    /// Counts commits by which `commit` is ahead of and behind `base`.
+    /// Returns None when the history needed is missing from a shallow or partial clone.
+    pub fn divergence(
+        &self,
+        commit: &git2::Commit,
+        base: &git2::Commit,
+    ) -> Result<Option<Divergence>> {
+        let ahead_behind = self.inner.graph_ahead_behind(commit.id(), base.id());
+        let (ahead, behind) = match unless_missing(ahead_behind)? {
+            Some(counts) => counts,
+            None => return Ok(None),
+        };
+        Ok(Some(Divergence {
+            ahead,
+            behind,
+            merge_base: unless_missing(self.inner.merge_base(commit.id(), base.id()))?,
+        }))
+    }
+
+    /// Lists local branches with commits at their tips, sorted by name.
+    pub fn branches(&self) -> Result<Vec<(String, git2::Commit<'_>)>> {
+        let mut branches = Vec::new();
+        for branch_result in self.inner.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch_result?;
+            let name = String::from_utf8_lossy(branch.name_bytes()?).to_string();
+            branches.push((name, branch.get().peel_to_commit()?));
+        }
+        branches.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(branches)
+    }
+
+    /// Lists tags with the objects they point to, newest first according to `order`.
+    pub fn tags(&self, order: TagOrder) -> Result<Vec<TagInfo<'_>>> {
+        let mut tags = Vec::new();
+        for reference_result in self.inner.references_glob("refs/tags/*")? {
+            let reference = reference_result?;
+            tags.push(TagInfo {
+                name: String::from_utf8_lossy(reference.shorthand_bytes()).to_string(),
+                tag: reference.peel_to_tag().ok(),
+                target: reference.peel(git2::ObjectType::Any)?,
+            });
+        }
+        match order {
+            TagOrder::Semver => tags.sort_by_cached_key(|tag| {
+                (
+                    Reverse(parse_version(&tag.name)),
+                    Reverse(tag.time()),
+                    tag.name.clone(),
+                )
+            }),
+            TagOrder::Date => {
+                tags.sort_by_cached_key(|tag| (Reverse(tag.time()), tag.name.clone()))
+            }
+            TagOrder::Name => tags.sort_by(|a, b| b.name.cmp(&a.name)),
+        }
+        Ok(tags)
+    }
+
+    /// Lists tags of commits as releases, newest first according to `order`,
+    /// with the commits of each one within limits given by `options`.
+    /// When ordering by version, tags that are not versions are left out.
+    pub fn releases(&self, order: TagOrder, options: &LogOptions) -> Result<Vec<Release<'_>>> {
+        let tags: Vec<_> = self
+            .tags(order)?
+            .into_iter()
+            .rev()
+            .filter(|tag| order != TagOrder::Semver || parse_version(&tag.name).is_some())
+            .filter_map(|tag| {
+                let commit = tag.target.as_commit()?.clone();
+                Some((tag, commit))
+            })
+            .collect();
+        let mut releases = Vec::with_capacity(tags.len());
+        let mut previous: Option<(String, git2::Commit)> = None;
+        for (tag, commit) in tags {
+            let hidden: Vec<_> = previous.iter().map(|(_, previous)| previous.id()).collect();
+            let commits = self.commits_between(commit.id(), &hidden, options)?;
+            let current = (tag.name.clone(), commit.clone());
+            releases.push(Release {
+                tag,
+                commit,
+                previous: previous.replace(current),
+                commits,
+            });
+        }
+        releases.reverse();
+        Ok(releases)
+    }
+
+    /// Finds branches and tags related to commits in `pages`, walking the history once in each direction.
+    pub fn containment(&self, pages: &std::collections::HashSet<git2::Oid>) -> Result<Containment> {
+        let mut tips_at = std::collections::HashMap::<git2::Oid, Vec<usize>>::new();
+        let mut branch_names = Vec::new();
+        for (index, (name, tip)) in self.branches()?.into_iter().enumerate() {
+            tips_at.entry(tip.id()).or_default().push(index);
+            branch_names.push(name);
+        }
+        // Oldest tags first, so that they win ties for the closest tag including a commit.
+        let mut tags_at = std::collections::HashMap::<git2::Oid, Vec<usize>>::new();
+        let mut tag_names = Vec::new();
+        for tag in self.tags(TagOrder::Date)?.into_iter().rev() {
+            if let Some(commit) = tag.target.as_commit() {
+                tags_at
+                    .entry(commit.id())
+                    .or_default()
+                    .push(tag_names.len());
+                tag_names.push(tag.name);
+            }
+        }
+        let tips: Vec<_> = tips_at.keys().chain(tags_at.keys()).copied().collect();
+        let order = if self.shallow.is_empty() {
+            let mut walk = self.inner.revwalk()?;
+            walk.set_sorting(git2::Sort::TOPOLOGICAL)?;
+            for &tip in &tips {
+                walk.push(tip)?;
+            }
+            walk.collect::<Result<Vec<_>, _>>()?
+        } else {
+            ShallowWalk::new(self, tips.into_iter(), false).topological()?
+        };
+        let parents_of = |oid: git2::Oid| -> Result<Vec<git2::Oid>> {
+            if self.shallow.contains(&oid) {
+                return Ok(vec![]);
+            }
+            Ok(self.inner.find_commit(oid)?.parent_ids().collect())
+        };
+
+        let mut commits = std::collections::HashMap::<git2::Oid, CommitRefs>::new();
+        // Children come before parents, so what reaches a commit is complete once it is its turn.
+        let mut pending = std::collections::HashMap::<git2::Oid, Reach>::new();
+        for &oid in &order {
+            let mut reach = pending
+                .remove(&oid)
+                .unwrap_or_else(|| Reach::new(branch_names.len()));
+            for &index in tips_at.get(&oid).into_iter().flatten() {
+                reach.branches[index / 64] |= 1 << (index % 64);
+            }
+            if let Some(&index) = tags_at.get(&oid).and_then(|indexes| indexes.first()) {
+                reach.tag = Some((0, index));
+            }
+            if pages.contains(&oid) {
+                let refs = commits.entry(oid).or_default();
+                refs.branches = (0..branch_names.len())
+                    .filter(|index| reach.branches[index / 64] & (1 << (index % 64)) != 0)
+                    .collect();
+                refs.contained_in = reach.tag.map(|(_, index)| index);
+            }
+            let mut inherited = reach;
+            inherited.tag = inherited.tag.map(|(distance, index)| (distance + 1, index));
+            for parent in parents_of(oid)? {
+                match pending.get_mut(&parent) {
+                    Some(parent_reach) => parent_reach.merge(&inherited),
+                    None => {
+                        pending.insert(parent, inherited.clone());
+                    }
+                }
+            }
+        }
+
+        // Parents come before children, and the newest of tags on the same commit wins.
+        let mut preceding = std::collections::HashMap::<git2::Oid, (usize, usize)>::new();
+        for &oid in order.iter().rev() {
+            let closest = match tags_at.get(&oid).and_then(|indexes| indexes.last()) {
+                Some(&index) => Some((0, index)),
+                None => parents_of(oid)?
+                    .iter()
+                    .filter_map(|parent| preceding.get(parent))
+                    .map(|&(distance, index)| (distance + 1, index))
+                    .min_by_key(|&(distance, index)| (distance, std::cmp::Reverse(index))),
+            };
+            if let Some((distance, index)) = closest {
+                preceding.insert(oid, (distance, index));
+                if pages.contains(&oid) {
+                    commits.entry(oid).or_default().described_by = Some((index, distance));
+                }
+            }
+        }
+        Ok(Containment {
+            branch_names,
+            tag_names,
+            commits,
+        })
+    }
+
+    /// Reads notes attached to a commit under `refs/notes/commits` and each of `notes_refs`,
+    /// returning the notes ref together with the note.
+    pub fn notes(&self, oid: git2::Oid, notes_refs: &[String]) -> Result<Vec<(String, String)>> {
+        let mut refs = vec!["refs/notes/commits".to_string()];
+        for notes_ref in notes_refs {
+            let notes_ref = if notes_ref.starts_with("refs/") {
+                notes_ref.clone()
+            } else {
+                format!("refs/notes/{}", notes_ref)
+            };
+            if !refs.contains(&notes_ref) {
+                refs.push(notes_ref);
+            }
+        }
+        let mut notes = Vec::new();
+        for notes_ref in refs {
+            if let Some(note) = unless_missing(self.inner.find_note(Some(&notes_ref), oid))? {
+                let message = String::from_utf8_lossy(note.message_bytes()).into_owned();
+                notes.push((notes_ref, message));
+            }
+        }
+        Ok(notes)
+    }
+
+    /// Resolves two revisions and lists commits made on top of the first one to reach the second,
+    /// within limits given by `options`.
+    pub fn compare(&self, from: &str, to: &str, options: &LogOptions) -> Result<Comparison<'_>> {
+        let from_commit = self.inner.revparse_single(from)?.peel_to_commit()?;
+        let to_commit = self.inner.revparse_single(to)?.peel_to_commit()?;
+        let commits = self.commits_between(to_commit.id(), &[from_commit.id()], options)?;
+        Ok(Comparison {
+            from: from.to_string(),
+            to: to.to_string(),
+            from_commit,
+            to_commit,
+            commits,
+        })
+    }
+
+    /// Lists commits reachable from `tip` but not from any of `hidden`, newest first, like `git log ^hidden tip`.
+    /// Only count and date limits of `options` apply.
+    pub fn commits_between(
+        &self,
+        tip: git2::Oid,
+        hidden: &[git2::Oid],
+        options: &LogOptions,
+    ) -> Result<CommitLog> {
+        if self.shallow.is_empty() {
+            let mut walk = self.inner.revwalk()?;
+            walk.push(tip)?;
+            for &hidden in hidden {
+                walk.hide(hidden)?;
+            }
+            return self.limit_log(walk, options);
+        }
+        let hidden: std::collections::HashSet<_> =
+            ShallowWalk::new(self, hidden.iter().copied(), false).collect::<Result<_, _>>()?;
+        let walk = ShallowWalk::new(self, std::iter::once(tip), false)
+            .filter(|oid| !matches!(oid, Ok(oid) if hidden.contains(oid)));
+        self.limit_log(walk, options)
+    }
+
+    /// Lists commits reachable from HEAD, newest first, within limits given by `options`.
+    pub fn commit_log(&self, options: &LogOptions) -> Result<CommitLog> {
+        self.commit_log_from(self.head_commit()?, options)
+    }
+
+    /// Lists commits reachable from the tip of a local branch, newest first.
+    pub fn branch_log(&self, branch: &str, options: &LogOptions) -> Result<CommitLog> {
+        let tip = self
+            .inner
+            .find_branch(branch, git2::BranchType::Local)?
+            .get()
+            .peel_to_commit()?;
+        self.commit_log_from(Some(tip), options)
+    }
+
+    fn commit_log_from(
+        &self,
+        head: Option<git2::Commit>,
+        options: &LogOptions,
+    ) -> Result<CommitLog> {
+        // Logs that are not reversed get drawn as a graph, which needs every parent to come
+        // after its children, whatever the clocks of their authors said.
+        let topological = options.order == LogOrder::Topological || !options.reverse;
+        let mut log_walk: Box<dyn Iterator<Item = Result<git2::Oid, git2::Error>>> =
+            if self.shallow.is_empty() {
+                let mut log_walk = self.inner.revwalk()?;
+                log_walk.set_sorting(match (options.order, topological) {
+                    (LogOrder::Time, true) => git2::Sort::TOPOLOGICAL | git2::Sort::TIME,
+                    (LogOrder::Time, false) => git2::Sort::TIME,
+                    (_, true) => git2::Sort::TOPOLOGICAL,
+                    (_, false) => git2::Sort::NONE,
+                })?;
+                if options.first_parent {
+                    log_walk.simplify_first_parent()?;
+                }
+                if let Some(head) = &head {
+                    log_walk.push(head.id())?;
+                }
+                Box::new(log_walk)
+            } else {
+                let tips = head.iter().map(|head| head.id());
+                let walk = ShallowWalk::new(self, tips, options.first_parent);
+                if topological {
+                    Box::new(walk.topological()?.into_iter().map(Ok))
+                } else {
+                    Box::new(walk)
+                }
+            };
+        let mut log = self.limit_log(&mut log_walk, options)?;
+        if options.reverse {
+            log.oids.reverse();
+        }
+        Ok(log)
+    }
+
+    /// Takes commits from a walk going from the newest ones, within the limits of `options`.
+    fn limit_log<I>(&self, walk: I, options: &LogOptions) -> Result<CommitLog>
+    where
+        I: Iterator<Item = Result<git2::Oid, git2::Error>>,
+    {
+        let mut log = CommitLog::default();
+        for oid_result in walk {
             let oid = oid_result?;
-            let commit = self.inner.find_commit(oid)?;
-            let tree = commit.tree()?;
-            let parent_tree = commit
+            let time = self.inner.find_commit(oid)?.time().seconds();
+            if options.until.is_some_and(|until| time > until) {
+                continue;
+            }
+            if options.since.is_some_and(|since| time < since) {
+                log.truncated = true;
+                // Only a walk by time is done once it reaches an old commit; in other orders
+                // newer commits may still follow it.
+                if options.order == LogOrder::Time {
+                    break;
+                }
+                continue;
+            }
+            if options.max_count.is_some_and(|max| log.oids.len() >= max) {
+                log.truncated = true;
+                break;
+            }
+            log.oids.push(oid);
+        }
+        Ok(log)
+    }
+
+    /// Gathers a commit together with its changes against the first parent.
+    pub fn commit_info(&self, oid: git2::Oid) -> Result<CommitInfo<'_>> {
+        let commit = self.inner.find_commit(oid)?;
+        let tree = commit.tree()?;
+        let shallow = self.shallow.contains(&oid);
+        let parent_tree = if shallow {
+            Some(tree.clone())
+        } else {
+            commit
                 .parents()
                 .next()
-                .and_then(|parent| parent.tree().ok());
-            let diff = self
-                .inner
-                .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
-            Ok(CommitInfo { commit, diff })
-        }))
+                .and_then(|parent| parent.tree().ok())
+        };
+        let diff = self
+            .inner
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+        Ok(CommitInfo {
+            commit,
+            diff,
+            shallow,
+        })
+    }
+
+    pub fn commit_infos<'a>(
+        &'a self,
+        oids: &'a [git2::Oid],
+    ) -> impl Iterator<Item = Result<CommitInfo<'a>>> + 'a {
+        oids.iter().map(move |&oid| self.commit_info(oid))
+    }
+
+    /// Compares the tree of `to` against the tree of `from`, or against an empty tree.
+    pub fn diff_commits(
+        &self,
+        from: Option<&git2::Commit>,
+        to: &git2::Commit,
+    ) -> Result<git2::Diff<'_>> {
+        let from_tree = match from {
+            Some(from) => Some(from.tree()?),
+            None => None,
+        };
+        let diff = self
+            .inner
+            .diff_tree_to_tree(from_tree.as_ref(), Some(&to.tree()?), None)?;
+        Ok(diff)
+    }
+
+    /// Resolves target of a symbolic link found at `link_path` (relative to the root of `tree`).
+    pub fn resolve_symlink(
+        &self,
+        tree: &git2::Tree,
+        link_path: &std::path::Path,
+        target: &std::path::Path,
+    ) -> SymlinkTarget {
+        use std::path::Component;
+        let mut resolved = link_path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+        for component in target.components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !resolved.pop() {
+                        return SymlinkTarget::OutOfTree;
+                    }
+                }
+                Component::RootDir | Component::Prefix(_) => return SymlinkTarget::OutOfTree,
+            }
+        }
+        if resolved.as_os_str().is_empty() || tree.get_path(&resolved).is_ok() {
+            SymlinkTarget::Inside(resolved)
+        } else {
+            SymlinkTarget::Broken
+        }
+    }
+
+    /// Lists submodules declared in `.gitmodules` at the root of `tree`.
+    pub fn submodules(&self, tree: &git2::Tree) -> Vec<Submodule> {
+        tree.get_path(std::path::Path::new(".gitmodules"))
+            .and_then(|entry| entry.to_object(&self.inner))
+            .and_then(|object| object.peel_to_blob())
+            .map(|blob| parse_gitmodules(&String::from_utf8_lossy(blob.content())))
+            .unwrap_or_default()
+    }
+
+    /// Reads the value of gitattribute `name` for `path`, using `.gitattributes` files stored in `tree`.
+    pub fn attribute(
+        &self,
+        tree: &git2::Tree,
+        path: &std::path::Path,
+        name: &str,
+    ) -> Option<String> {
+        let mut value = None;
+        for dir in path
+            .ancestors()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            let content = tree
+                .get_path(&dir.join(".gitattributes"))
+                .and_then(|entry| entry.to_object(&self.inner))
+                .and_then(|object| object.peel_to_blob());
+            if let Ok(blob) = content {
+                let content = String::from_utf8_lossy(blob.content());
+                if let Some(found) = lookup_gitattributes(&content, dir, path, name) {
+                    value = found;
+                }
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(name: &str) -> Option<String> {
+        parse_version(name).map(|version| version.to_string())
+    }
+
+    #[test]
+    fn missing_components_are_filled_in() {
+        assert_eq!(version("v1.2").as_deref(), Some("1.2.0"));
+        assert_eq!(version("v3").as_deref(), Some("3.0.0"));
+    }
+
+    #[test]
+    fn pre_releases_are_kept() {
+        assert_eq!(version("1.2.3-rc.1").as_deref(), Some("1.2.3-rc.1"));
+        assert!(parse_version("1.2.3-rc.1") < parse_version("1.2.3"));
+    }
+
+    #[test]
+    fn prefixes_are_skipped() {
+        assert_eq!(version("release-2.0").as_deref(), Some("2.0.0"));
+    }
+
+    #[test]
+    fn leading_zeros_are_dropped() {
+        assert_eq!(version("2021.01.05").as_deref(), Some("2021.1.5"));
+        assert_eq!(version("v1.0.00").as_deref(), Some("1.0.0"));
+    }
+
+    #[test]
+    fn numbers_compare_numerically() {
+        assert!(parse_version("v1.10") > parse_version("v1.9"));
+    }
+
+    #[test]
+    fn other_names_are_not_versions() {
+        assert_eq!(version("nightly"), None);
+        assert_eq!(version("1.2.3.4"), None);
+        assert_eq!(version("v1.x"), None);
+    }
+
+    /// Builds a history in a fresh bare repository, which callers remove when done, and returns
+    /// it with its commits by message. Every commit is given as its message, commit time in days
+    /// and parent messages, parents first; `master` points at the last one.
+    fn history<'c>(
+        name: &str,
+        commits: &[(&'c str, i64, &[&str])],
+    ) -> (Repository, std::collections::HashMap<&'c str, git2::Oid>) {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!(
+            "rustagit-{}-{}-{}",
+            name,
+            std::process::id(),
+            count
+        ));
+        let inner = git2::Repository::init_bare(&path).unwrap();
+        let tree = inner
+            .find_tree(inner.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let mut oids = std::collections::HashMap::new();
+        for &(message, day, parents) in commits {
+            let signature =
+                git2::Signature::new("a", "a@example.com", &git2::Time::new(day * 86400, 0))
+                    .unwrap();
+            let parents: Vec<_> = parents
+                .iter()
+                .map(|parent| inner.find_commit(oids[parent]).unwrap())
+                .collect();
+            let parents: Vec<_> = parents.iter().collect();
+            let oid = inner
+                .commit(None, &signature, &signature, message, &tree, &parents)
+                .unwrap();
+            oids.insert(message, oid);
+        }
+        let tip = oids[commits.last().unwrap().0];
+        inner.reference("refs/heads/master", tip, true, "").unwrap();
+        (Repository::open(&path, None).unwrap(), oids)
+    }
+
+    fn messages(repository: &Repository, log: &CommitLog) -> Vec<String> {
+        log.oids
+            .iter()
+            .map(|&oid| {
+                let commit = repository.inner.find_commit(oid).unwrap();
+                commit.summary().unwrap().to_owned()
+            })
+            .collect()
+    }
+
+    /// A branch whose last commit claims to be older than the commit it was forked from.
+    const SKEWED: &[(&str, i64, &[&str])] = &[
+        ("A", 10, &[]),
+        ("B1", 20, &["A"]),
+        ("B2", 5, &["B1"]),
+        ("M1", 30, &["A"]),
+        ("merge", 40, &["M1", "B2"]),
+    ];
+
+    #[test]
+    fn logs_keep_parents_after_children() {
+        let (repository, _) = history("log-order", SKEWED);
+        for order in [LogOrder::Default, LogOrder::Time, LogOrder::Topological] {
+            let options = LogOptions {
+                order,
+                ..LogOptions::default()
+            };
+            let log = messages(&repository, &repository.commit_log(&options).unwrap());
+            let position = |message| log.iter().position(|m| m == message).unwrap();
+            assert!(position("B2") < position("B1"), "{:?}: {:?}", order, log);
+            assert!(position("B1") < position("A"), "{:?}: {:?}", order, log);
+            assert!(position("M1") < position("A"), "{:?}: {:?}", order, log);
+        }
+        std::fs::remove_dir_all(&repository.path).unwrap();
+    }
+
+    #[test]
+    fn since_keeps_newer_commits_behind_older_ones() {
+        let (repository, _) = history(
+            "since",
+            &[
+                ("base", 10, &[]),
+                ("side", 20, &["base"]),
+                ("main-new", 40, &["base"]),
+                ("merge", 41, &["main-new", "side"]),
+                ("tip", 42, &["merge"]),
+            ],
+        );
+        let options = LogOptions {
+            since: Some(30 * 86400),
+            order: LogOrder::Topological,
+            ..LogOptions::default()
+        };
+        let log = repository.commit_log(&options).unwrap();
+        assert_eq!(messages(&repository, &log), ["tip", "merge", "main-new"]);
+        assert!(log.truncated);
+        std::fs::remove_dir_all(&repository.path).unwrap();
+    }
+
+    #[test]
+    fn stars_stay_within_a_directory() {
+        assert!(glob_match(b"*.txt", b"notes.txt"));
+        assert!(!glob_match(b"*.txt", b"docs/notes.txt"));
+        assert!(glob_match(b"dir/*.txt", b"dir/notes.txt"));
+        assert!(!glob_match(b"dir/*.txt", b"dir/sub/notes.txt"));
+        assert!(!glob_match(b"dir/*.txt", b"other/notes.txt"));
+        assert!(glob_match(b"a?c", b"abc"));
+        assert!(!glob_match(b"a?c", b"a/c"));
+    }
+
+    #[test]
+    fn double_stars_match_whole_directories() {
+        assert!(glob_match(b"**/foo", b"foo"));
+        assert!(glob_match(b"**/foo", b"bar/baz/foo"));
+        assert!(!glob_match(b"**/foo", b"xfoo"));
+        assert!(!glob_match(b"**/foo", b"bar/xfoo"));
+        assert!(glob_match(b"a/**/b", b"a/b"));
+        assert!(glob_match(b"a/**/b", b"a/x/y/b"));
+        assert!(!glob_match(b"a/**/b", b"a/xb"));
+        assert!(glob_match(b"dir/**", b"dir/sub/file"));
+        assert!(glob_match(b"a**b", b"axxb"));
+        assert!(!glob_match(b"a**b", b"ax/b"));
+    }
+
+    fn encoding_of(content: &str, dir: &str, path: &str) -> Option<Option<String>> {
+        lookup_gitattributes(
+            content,
+            std::path::Path::new(dir),
+            std::path::Path::new(path),
+            "working-tree-encoding",
+        )
+    }
+
+    #[test]
+    fn attributes_follow_patterns() {
+        let content = "# comment\n*.txt working-tree-encoding=UTF-16\nlegacy/*.txt working-tree-encoding=CP1252\n";
+        assert_eq!(
+            encoding_of(content, "", "docs/a.txt"),
+            Some(Some("UTF-16".to_string()))
+        );
+        assert_eq!(
+            encoding_of(content, "", "legacy/a.txt"),
+            Some(Some("CP1252".to_string()))
+        );
+        assert_eq!(
+            encoding_of(content, "sub", "sub/legacy/a.txt"),
+            Some(Some("CP1252".to_string()))
+        );
+        assert_eq!(encoding_of(content, "", "a.rs"), None);
+        assert_eq!(encoding_of(content, "other", "sub/a.txt"), None);
+    }
+
+    #[test]
+    fn last_matching_line_wins() {
+        let content = "*.txt working-tree-encoding=UTF-16\nplain.txt -working-tree-encoding\n";
+        assert_eq!(encoding_of(content, "", "plain.txt"), Some(None));
+        assert_eq!(
+            encoding_of(content, "", "other.txt"),
+            Some(Some("UTF-16".to_string()))
+        );
+        let content = "plain.txt -working-tree-encoding\n*.txt working-tree-encoding=UTF-16\n";
+        assert_eq!(
+            encoding_of(content, "", "plain.txt"),
+            Some(Some("UTF-16".to_string()))
+        );
+    }
+
+    #[test]
+    fn containment_matches_git_describe() {
+        let (repository, oids) = history(
+            "containment",
+            &[
+                ("A", 1, &[]),
+                ("B", 2, &["A"]),
+                ("C", 3, &["B"]),
+                ("D", 4, &["B"]),
+                ("E", 5, &["D"]),
+                ("M", 6, &["C", "E"]),
+                ("N", 7, &["M"]),
+            ],
+        );
+        let inner = &repository.inner;
+        for (branch, commit) in [("old", "C"), ("side", "E")] {
+            inner
+                .branch(branch, &inner.find_commit(oids[commit]).unwrap(), false)
+                .unwrap();
+        }
+        // Two tags on M, so that the older one names it for --contains and the newer one
+        // describes it.
+        for (tag, commit, day) in [
+            ("v1", "B", 2),
+            ("v2", "E", 5),
+            ("v3.0", "M", 10),
+            ("v3-final", "M", 11),
+        ] {
+            let tagger =
+                git2::Signature::new("a", "a@example.com", &git2::Time::new(day * 86400, 0))
+                    .unwrap();
+            let target = inner.find_object(oids[commit], None).unwrap();
+            inner.tag(tag, &target, &tagger, tag, false).unwrap();
+        }
+        let containment = repository
+            .containment(&oids.values().copied().collect())
+            .unwrap();
+        // What `git branch --contains`, `git describe --contains` and `git describe` say.
+        type Refs<'a> = (&'a [&'a str], Option<&'a str>, Option<(&'a str, usize)>);
+        let expected: &[(&str, Refs)] = &[
+            ("A", (&["master", "old", "side"], Some("v1"), None)),
+            (
+                "B",
+                (&["master", "old", "side"], Some("v1"), Some(("v1", 0))),
+            ),
+            ("C", (&["master", "old"], Some("v3.0"), Some(("v1", 1)))),
+            ("D", (&["master", "side"], Some("v2"), Some(("v1", 1)))),
+            ("E", (&["master", "side"], Some("v2"), Some(("v2", 0)))),
+            ("M", (&["master"], Some("v3.0"), Some(("v3-final", 0)))),
+            ("N", (&["master"], None, Some(("v3-final", 1)))),
+        ];
+        for &(commit, (branches, contained_in, described_by)) in expected {
+            let oid = oids[commit];
+            let mut found: Vec<_> = containment.branches_containing(oid).collect();
+            found.sort_unstable();
+            assert_eq!(found, branches, "{}", commit);
+            assert_eq!(
+                containment.first_tag_containing(oid),
+                contained_in,
+                "{}",
+                commit
+            );
+            assert_eq!(containment.describe(oid), described_by, "{}", commit);
+        }
+        std::fs::remove_dir_all(&repository.path).unwrap();
+    }
+
+    #[test]
+    fn containment_covers_only_pages() {
+        let (repository, oids) = history("containment-pages", &[("A", 1, &[]), ("B", 2, &["A"])]);
+        let inner = &repository.inner;
+        let target = inner.find_object(oids["A"], None).unwrap();
+        inner.tag_lightweight("v1", &target, false).unwrap();
+        let pages = std::iter::once(oids["B"]).collect();
+        let containment = repository.containment(&pages).unwrap();
+        assert_eq!(containment.describe(oids["B"]), Some(("v1", 1)));
+        assert_eq!(containment.describe(oids["A"]), None);
+        assert_eq!(containment.branches_containing(oids["A"]).count(), 0);
+        std::fs::remove_dir_all(&repository.path).unwrap();
     }
 }
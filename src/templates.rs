@@ -2,8 +2,12 @@ use std::fmt::Display;
 use std::io::Write;
 use std::os::unix::ffi::OsStrExt;
 
-use crate::repository::{CommitInfo, Repository};
-use crate::InvalidUtf;
+use crate::graph::{self, GraphRow, Level};
+use crate::media::{human_size, Media, MediaKind};
+use crate::repository::{
+    commit_time, signature_time, unless_missing, CommitInfo, CommitLog, Comparison, Containment,
+    LogOptions, Release, Repository, Submodule, SymlinkTarget, TagInfo, TagOrder, FILEMODE_SYMLINK,
+};
 use anyhow::Result;
 use fs_err as fs;
 use maud::html;
@@ -18,7 +22,7 @@ impl UrlResolver {
         Self { base }
     }
 
-    fn join<P: AsRef<std::path::Path>>(&self, path: P) -> Self {
+    pub fn join<P: AsRef<std::path::Path>>(&self, path: P) -> Self {
         Self {
             base: self.base.join(path),
         }
@@ -47,6 +51,35 @@ impl UrlResolver {
         self.join("log.html")
     }
 
+    pub fn commit_log_dir(&self) -> Self {
+        self.join("log")
+    }
+
+    /// Page of the commit log; the first one stays at `log.html`.
+    pub fn commit_log_page(&self, page: usize) -> Self {
+        if page <= 1 {
+            self.commit_log()
+        } else {
+            self.commit_log_dir().join(format!("{}.html", page))
+        }
+    }
+
+    /// Page of the commit log of a branch, or of HEAD if no branch is given.
+    /// Git does not allow branches `a` and `a/2` to coexist, so pages cannot collide.
+    pub fn log_page(&self, branch: Option<&str>, page: usize) -> Self {
+        match branch {
+            None => self.commit_log_page(page),
+            Some(branch) if page <= 1 => {
+                self.commit_log_dir().join("branch").join(branch).dot_html()
+            }
+            Some(branch) => self
+                .commit_log_dir()
+                .join("branch")
+                .join(branch)
+                .join(format!("{}.html", page)),
+        }
+    }
+
     pub fn tree_dir(&self) -> Self {
         self.join("tree")
     }
@@ -55,10 +88,41 @@ impl UrlResolver {
         self.tree_dir().dot_html()
     }
 
-    pub fn tree_file(&self, name: &str) -> Self {
+    pub fn tree_file<P: AsRef<std::path::Path>>(&self, name: P) -> Self {
         self.tree_dir().join(name).dot_html()
     }
 
+    /// Page of a file or directory found at `path` relative to the root of the tree.
+    pub fn tree_node<P: AsRef<std::path::Path>>(&self, path: P) -> Self {
+        if path.as_ref().as_os_str().is_empty() {
+            self.tree_index()
+        } else {
+            self.tree_file(path)
+        }
+    }
+
+    pub fn raw_dir(&self) -> Self {
+        self.join("raw")
+    }
+
+    pub fn tag_dir(&self) -> Self {
+        self.join("tag")
+    }
+
+    pub fn tag_file(&self, name: &str) -> Self {
+        self.tag_dir().join(name).dot_html()
+    }
+
+    pub fn releases(&self) -> Self {
+        self.join("releases.html")
+    }
+
+    pub fn compare_file(&self, from: &str, to: &str) -> Self {
+        self.join("compare")
+            .join(format!("{}..{}", from, to))
+            .dot_html()
+    }
+
     pub fn refs_list(&self) -> Self {
         self.join("refs.html")
     }
@@ -86,17 +150,137 @@ impl AsRef<std::path::Path> for UrlResolver {
     }
 }
 
+/// Bytes that have to be escaped in a path segment of an URL.
+const PATH_SEGMENT: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b'+')
+    .remove(b',')
+    .remove(b';')
+    .remove(b'=')
+    .remove(b':')
+    .remove(b'@');
+
+/// Displays the path as an URL, percent-encoding each of its segments.
+/// Use `AsRef<Path>` to get the raw filesystem path instead.
 impl Display for UrlResolver {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.base.to_string_lossy())
+        let mut segments = self.base.as_os_str().as_bytes().split(|&c| c == b'/');
+        if let Some(first) = segments.next() {
+            write!(
+                f,
+                "{}",
+                percent_encoding::percent_encode(first, PATH_SEGMENT)
+            )?;
+        }
+        for segment in segments {
+            write!(
+                f,
+                "/{}",
+                percent_encoding::percent_encode(segment, PATH_SEGMENT)
+            )?;
+        }
+        Ok(())
     }
 }
 
+/// Turns a tree entry name into a file name that stays inside its directory,
+/// whatever bytes the name is made of.
+fn safe_file_name(name: &[u8]) -> std::ffi::OsString {
+    use std::os::unix::ffi::OsStringExt;
+    let escaped = match name {
+        b"" => b"%".to_vec(),
+        b"." => b"%2E".to_vec(),
+        b".." => b"%2E%2E".to_vec(),
+        _ => name
+            .iter()
+            .flat_map(|&c| match c {
+                b'/' => b"%2F".to_vec(),
+                0 => b"%00".to_vec(),
+                c => vec![c],
+            })
+            .collect(),
+    };
+    std::ffi::OsString::from_vec(escaped)
+}
+
+/// Tells whether a file is written in Markdown, judging by its extension.
+fn is_markdown(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ["md", "markdown", "mkd", "mdown"]
+                .iter()
+                .any(|md| extension.eq_ignore_ascii_case(md))
+        })
+}
+
+/// Picks output file names for entries of a tree, in tree order. Every entry claims its page
+/// (`name.html`) and either its directory or its raw copy (`name`). Claims are compared
+/// case-insensitively, and an entry whose claims are taken gets a `~1`, `~2`... suffix before
+/// its extension, so no two entries overwrite each other even on case-insensitive filesystems.
+fn output_names(tree: &git2::Tree) -> Vec<std::ffi::OsString> {
+    let mut claimed = std::collections::HashSet::new();
+    let fold = |name: &[u8]| String::from_utf8_lossy(name).to_lowercase();
+    tree.iter()
+        .map(|entry| {
+            let name = safe_file_name(entry.name_bytes());
+            let mut candidate = name.clone();
+            let mut counter = 0;
+            loop {
+                let bare = fold(candidate.as_bytes());
+                let page = format!("{}.html", bare);
+                if !claimed.contains(&bare) && !claimed.contains(&page) {
+                    claimed.insert(bare);
+                    claimed.insert(page);
+                    return candidate;
+                }
+                counter += 1;
+                let path = std::path::Path::new(&name);
+                candidate = match (path.file_stem(), path.extension()) {
+                    (Some(stem), Some(extension)) => {
+                        let mut candidate = stem.to_os_string();
+                        candidate.push(format!("~{}.", counter));
+                        candidate.push(extension);
+                        candidate
+                    }
+                    _ => {
+                        let mut candidate = name.clone();
+                        candidate.push(format!("~{}", counter));
+                        candidate
+                    }
+                };
+            }
+        })
+        .collect()
+}
+
 pub struct Templator<'a> {
     pub(crate) repository: Repository,
     pub(crate) url: UrlResolver,
     pub(crate) syntax_set: syntect::parsing::SyntaxSet,
     pub(crate) theme: &'a syntect::highlighting::Theme,
+    pub(crate) submodule_pages: Option<String>,
+    pub(crate) raw_dir: bool,
+    pub(crate) log_options: LogOptions,
+    pub(crate) log_page_size: Option<usize>,
+    pub(crate) log_branches: Vec<String>,
+    pub(crate) tag_order: TagOrder,
+    /// Pairs of revisions to generate compare pages for, besides consecutive releases.
+    pub(crate) compare_pairs: Vec<(String, String)>,
+    /// Age after which branches without new commits count as stale.
+    pub(crate) stale_after: std::time::Duration,
+    /// Notes refs to show besides `refs/notes/commits`.
+    pub(crate) notes_refs: Vec<String>,
 }
 
 impl Templator<'_> {
@@ -107,6 +291,30 @@ impl Templator<'_> {
         td.numeric {
             font-family: monospace;
         }
+        figure img, figure video, article.readme img {
+            max-width: 100%;
+        }
+        table.log td {
+            white-space: nowrap;
+        }
+        td.graph {
+            padding: 0;
+        }
+        td.graph svg {
+            display: block;
+            height: 100%;
+            min-height: 1.5em;
+        }
+        td.graph line {
+            stroke: #888;
+            stroke-width: 2;
+        }
+        td.graph circle {
+            fill: #444;
+        }
+        .broken {
+            text-decoration: line-through;
+        }
     "#;
 
     fn write_default_css_if_not_exists(&self) -> Result<()> {
@@ -150,7 +358,8 @@ impl Templator<'_> {
                         ul.inline {
                             li { a href=(the_way_out.commit_log()) { "Commits" } }
                             li { a href=(the_way_out.tree_index()) { "Files" } }
-                            // TODO: li { a href=(the_way_out.refs_list()) { "Branches and tags" } }
+                            li { a href=(the_way_out.refs_list()) { "Branches and tags" } }
+                            li { a href=(the_way_out.releases()) { "Releases" } }
                         }
                     }
                     main { (content) }
@@ -175,47 +384,187 @@ impl Templator<'_> {
         Ok(())
     }
 
-    fn write_commit_log(&self) -> Result<()> {
-        let log_path = self.url.commit_log();
+    /// Selects the commits of the log of a branch, or of HEAD if no branch is given.
+    fn commit_log_of(&self, branch: Option<&str>) -> Result<CommitLog> {
+        match branch {
+            Some(branch) => self.repository.branch_log(branch, &self.log_options),
+            None => self.repository.commit_log(&self.log_options),
+        }
+    }
+
+    fn write_commit_log(&self, branch: Option<&str>) -> Result<()> {
+        let commit_log = self.commit_log_of(branch)?;
+        let page_size = self.log_page_size.unwrap_or(usize::MAX).max(1);
+        let pages: Vec<_> = commit_log.oids.chunks(page_size).collect();
+        let graph = if self.log_options.reverse {
+            Vec::new()
+        } else {
+            graph::layout(&commit_log.oids, |oid| self.graph_parents(oid))
+        };
+        let graph_pages: Vec<_> = graph.chunks(page_size).collect();
+        for page in 1..=pages.len().max(1) {
+            let oids = pages.get(page - 1).copied().unwrap_or_default();
+            let graph = graph_pages.get(page - 1).copied().unwrap_or_default();
+            let truncated = commit_log.truncated
+                && if self.log_options.reverse {
+                    page == 1
+                } else {
+                    page >= pages.len()
+                };
+            self.write_commit_log_page(branch, oids, graph, page, pages.len(), truncated)?;
+        }
+        Ok(())
+    }
+
+    /// Parents of a commit that the log follows.
+    fn graph_parents(&self, oid: git2::Oid) -> Vec<git2::Oid> {
+        let parent_count = if self.log_options.first_parent {
+            1
+        } else {
+            usize::MAX
+        };
+        match self.repository.inner.find_commit(oid) {
+            Ok(commit) => commit.parent_ids().take(parent_count).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Draws a row of the commit graph as inline SVG.
+    fn graph_row(row: &GraphRow) -> maud::Markup {
+        const LANE_WIDTH: usize = 12;
+        const ROW_HEIGHT: usize = 24;
+        let x = |lane: usize| lane * LANE_WIDTH + LANE_WIDTH / 2;
+        let y = |level: Level| match level {
+            Level::Top => 0,
+            Level::Middle => ROW_HEIGHT / 2,
+            Level::Bottom => ROW_HEIGHT,
+        };
+        let width = row.width * LANE_WIDTH;
+        html! {
+            svg width=(width) height=(ROW_HEIGHT) viewBox={"0 0 " (width) " " (ROW_HEIGHT)} preserveAspectRatio="none" {
+                @for segment in &row.segments {
+                    line x1=(x(segment.from.0)) y1=(y(segment.from.1)) x2=(x(segment.to.0)) y2=(y(segment.to.1)) {}
+                }
+                circle cx=(x(row.column)) cy=(y(Level::Middle)) r="4" {}
+            }
+        }
+    }
+
+    fn write_commit_log_page(
+        &self,
+        branch: Option<&str>,
+        oids: &[git2::Oid],
+        graph: &[GraphRow],
+        page: usize,
+        page_count: usize,
+        truncated: bool,
+    ) -> Result<()> {
+        let log_path = self.url.log_page(branch, page);
+        fs::create_dir_all(log_path.base.parent().unwrap())?;
+        let the_way_out = self.url.rel_root_from(&log_path);
+        let mut title = match branch {
+            Some(branch) => format!("Commit log of {}", branch),
+            None => "Commit log".to_string(),
+        };
+        if page_count > 1 {
+            title.push_str(&format!(", page {} of {}", page, page_count));
+        }
         let log = self.template_page(
-            "Commit log",
+            &title,
             &log_path.base,
             html! {
-                table {
-                    thead {
-                        tr {
-                            th { "Date" }
-                            th { "Commit message" }
-                            th { "Author" }
-                            th.numeric { "Files" }
-                            th.numeric { "+" }
-                            th.numeric { "-" }
+                @if !self.log_branches.is_empty() {
+                    nav.branches {
+                        "Branch: "
+                        ul.inline {
+                            li {
+                                @if branch.is_none() { strong { "HEAD" } }
+                                @else { a href=(the_way_out.log_page(None, 1)) { "HEAD" } }
+                            }
+                            @for log_branch in &self.log_branches {
+                                li {
+                                    @if branch == Some(log_branch.as_str()) { strong { (log_branch) } }
+                                    @else { a href=(the_way_out.log_page(Some(log_branch), 1)) { (log_branch) } }
+                                }
+                            }
                         }
                     }
-                    tbody {
-                        @for ci_result in self.repository.commit_log()? {
-                            @let ci = ci_result?;
-                            tr itemscope itemtype="http://schema.org/UpdateAction" {
-                                link itemprop="targetCollection" itemid="#repository";
-                                td {
-                                    abbr title={(ci.time())} itemprop="endTime" {
-                                        (ci.time().date().format("%Y-%m-%d"))
-                                    }
+                }
+                @if oids.is_empty() && !truncated {
+                    p { "No commits yet." }
+                } @else {
+                    @if truncated && self.log_options.reverse {
+                        p.omitted { "Older commits omitted." }
+                    }
+                    table.log {
+                        thead {
+                            tr {
+                                @if !graph.is_empty() {
+                                    th.graph {}
                                 }
-                                td {
-                                    a itemprop="url" href={"commit/" (ci.commit.id()) ".html"} {
-                                        span itemprop="description" { (ci.commit.summary().ok_or(InvalidUtf)?) }
+                                th { "Date" }
+                                th { "Commit message" }
+                                th { "Author" }
+                                th.numeric { "Files" }
+                                th.numeric { "+" }
+                                th.numeric { "-" }
+                            }
+                        }
+                        tbody {
+                            @for (index, ci_result) in self.repository.commit_infos(oids).enumerate() {
+                                @let ci = ci_result?;
+                                tr itemscope itemtype="http://schema.org/UpdateAction" {
+                                    link itemprop="targetCollection" itemid="#repository";
+                                    @if let Some(row) = graph.get(index) {
+                                        td.graph { (Self::graph_row(row)) }
                                     }
-                                }
-                                td itemprop="agent" itemscope itemtype="http://schema.org/Person" {
-                                    span itemprop="name" {
-                                        (ci.commit.author().name().ok_or(InvalidUtf)?)
+                                    td {
+                                        abbr title={(ci.time())} itemprop="endTime" {
+                                            (ci.time().date().format("%Y-%m-%d"))
+                                        }
+                                    }
+                                    td {
+                                        a itemprop="url" href=(the_way_out.commit_file(&ci.commit.id().to_string())) {
+                                            span itemprop="description" { (String::from_utf8_lossy(ci.commit.summary_bytes().unwrap_or_default())) }
+                                        }
+                                        @if ci.shallow {
+                                            " " small.shallow { "(shallow clone boundary)" }
+                                        }
+                                    }
+                                    td itemprop="agent" itemscope itemtype="http://schema.org/Person" {
+                                        span itemprop="name" {
+                                            (String::from_utf8_lossy(ci.commit.author().name_bytes()))
+                                        }
+                                    }
+                                    @match unless_missing(ci.diff.stats())? {
+                                        Some(diffstats) => {
+                                            td.numeric { (diffstats.files_changed()) }
+                                            td.numeric { (diffstats.insertions()) }
+                                            td.numeric { (diffstats.deletions()) }
+                                        }
+                                        None => td.numeric colspan="3" { "?" },
                                     }
                                 }
-                                @let diffstats = ci.diff.stats()?;
-                                td.numeric { (diffstats.files_changed()) }
-                                td.numeric { (diffstats.insertions()) }
-                                td.numeric { (diffstats.deletions()) }
+                            }
+                        }
+                    }
+                    @if truncated && !self.log_options.reverse {
+                        p.omitted { "Older commits omitted." }
+                    }
+                    @if page_count > 1 {
+                        nav.pagination {
+                            @if page > 1 {
+                                a rel="prev" href=(the_way_out.log_page(branch, page - 1)) {
+                                    @if self.log_options.reverse { "Older" } @else { "Newer" }
+                                }
+                                " "
+                            }
+                            "Page " (page) " of " (page_count)
+                            @if page < page_count {
+                                " "
+                                a rel="next" href=(the_way_out.log_page(branch, page + 1)) {
+                                    @if self.log_options.reverse { "Newer" } @else { "Older" }
+                                }
                             }
                         }
                     }
@@ -226,7 +575,13 @@ impl Templator<'_> {
         Ok(())
     }
 
-    pub fn write_commit(&self, ci: &CommitInfo) -> Result<()> {
+    /// Writes the page of a commit, linking only to pages of other commits found in `pages`.
+    pub fn write_commit(
+        &self,
+        ci: &CommitInfo,
+        pages: &std::collections::HashSet<git2::Oid>,
+        containment: &Containment,
+    ) -> Result<()> {
         let patch_path = self.url.commit_file(&ci.commit.id().to_string());
         let patch = self.template_page(
             &format!("Commit {}", ci.commit.id()),
@@ -236,18 +591,24 @@ impl Templator<'_> {
                     link itemprop="targetCollection" itemid="#repository";
                     dt { "commit" }
                     dd itemprop="identifier" { (ci.commit.id()) }
-                    @for parent in ci.commit.parents() {
+                    @for parent in ci.commit.parent_ids() {
                         dt { "parent" }
-                        dd { a href={(parent.id()) ".html"} { (parent.id()) } }
+                        @if ci.shallow {
+                            dd { (parent) " (not in this shallow clone)" }
+                        } @else if pages.contains(&parent) {
+                            dd { a href={(parent) ".html"} { (parent) } }
+                        } @else {
+                            dd { (parent) }
+                        }
                     }
                     dt { "author" }
                     dd itemprop="agent" itemscope itemtype="http://schema.org/Person" {
                         @let sig = ci.commit.author();
                         span itemprop="name" {
-                            (sig.name().ok_or(InvalidUtf)?)
+                            (String::from_utf8_lossy(sig.name_bytes()))
                         }
                         " <"
-                        @let email = sig.email().ok_or(InvalidUtf)?;
+                        @let email = String::from_utf8_lossy(sig.email_bytes());
                         a itemprop="email" href={"mailto:" (&email)} { (email) }
                         ">"
                     }
@@ -255,49 +616,724 @@ impl Templator<'_> {
                     dd itemprop="participant" itemscope itemtype="http://schema.org/Person" {
                         @let sig = ci.commit.committer();
                         span itemprop="name" {
-                            (sig.name().ok_or(InvalidUtf)?)
+                            (String::from_utf8_lossy(sig.name_bytes()))
                         }
                         " <"
-                        @let email = sig.email().ok_or(InvalidUtf)?;
+                        @let email = String::from_utf8_lossy(sig.email_bytes());
                         a itemprop="email" href={"mailto:" (&email)} { (email) }
                         ">"
                     }
                     dt { "message" }
                     dd {
-                        pre itemprop="description" { (ci.commit.message().ok_or(InvalidUtf)?) }
+                        pre itemprop="description" { (String::from_utf8_lossy(ci.commit.message_bytes())) }
                     }
-                    dt { "diffstat" }
-                    dd {
-                        pre {
-                            (ci.diff.stats()?.to_buf(git2::DiffStatsFormat::FULL, 72)?.as_str().ok_or(InvalidUtf)?)
+                    @for (notes_ref, note) in self.repository.notes(ci.commit.id(), &self.notes_refs)? {
+                        dt { "notes (" (notes_ref.strip_prefix("refs/notes/").unwrap_or(&notes_ref)) ")" }
+                        dd { pre { (note) } }
+                    }
+                    @let the_way_out = self.url.rel_root_from(&patch_path);
+                    @let branches: Vec<_> = containment.branches_containing(ci.commit.id()).collect();
+                    @if !branches.is_empty() {
+                        dt { "contained in" }
+                        dd {
+                            @for (index, branch) in branches.iter().enumerate() {
+                                @if index > 0 { ", " }
+                                @if self.log_branches.iter().any(|log_branch| log_branch == branch) {
+                                    a href=(the_way_out.log_page(Some(branch), 1)) { (branch) }
+                                } @else {
+                                    (branch)
+                                }
+                            }
                         }
                     }
-                }
-                @for (delta_id, _delta) in ci.diff.deltas().enumerate() {
-                    @let patch = git2::Patch::from_diff(&ci.diff, delta_id)?;
-                    @match patch {
-                        Some(mut patch) => {
-                            pre { (patch.to_buf()?.as_str().ok_or(InvalidUtf)?) }
+                    @if let Some(tag) = containment.first_tag_containing(ci.commit.id()) {
+                        dt { "first tag" }
+                        dd { a href=(the_way_out.tag_file(tag)) { (tag) } }
+                    }
+                    @if let Some((tag, distance)) = containment.describe(ci.commit.id()) {
+                        dt { "describe" }
+                        dd {
+                            a href=(the_way_out.tag_file(tag)) { (tag) }
+                            @if distance > 0 {
+                                "-" (distance) "-g" (ci.commit.id().to_string()[..7])
+                            }
+                        }
+                    }
+                    @let submodule_deltas: Vec<_> = ci.diff.deltas()
+                        .filter(|delta| delta.new_file().mode() == git2::FileMode::Commit || delta.old_file().mode() == git2::FileMode::Commit)
+                        .collect();
+                    @if !submodule_deltas.is_empty() {
+                        @let submodules = self.repository.submodules(&ci.commit.tree()?);
+                        dt { "submodules" }
+                        @for delta in submodule_deltas {
+                            dd {
+                                @let path = delta.new_file().path().or_else(|| delta.old_file().path()).unwrap_or_else(|| std::path::Path::new(""));
+                                @if delta.old_file().mode() == git2::FileMode::Commit {
+                                    code { (delta.old_file().id()) }
+                                } @else {
+                                    "added"
+                                }
+                                " -> "
+                                @if delta.new_file().mode() == git2::FileMode::Commit {
+                                    (self.submodule(&submodules, path, delta.new_file().id(), &the_way_out)?)
+                                } @else {
+                                    (path.to_string_lossy()) " removed"
+                                }
+                            }
                         }
-                        None => { "unchanged or binary" }
+                    }
+                    @if ci.shallow {
+                        dt { "diffstat" }
+                        dd { "History of this shallow clone ends here, so changes made by this commit are unknown." }
+                    } @else {
+                        dt { "diffstat" }
+                        dd { (Self::diffstat(&ci.diff)?) }
                     }
                 }
+                (Self::patches(&ci.diff)?)
             },
         )?;
         fs::write(patch_path.base, patch.into_string().as_bytes())?;
         Ok(())
     }
 
-    pub fn write_all_commits(&self) -> Result<()> {
-        for ci_result in self.repository.commit_log()? {
-            self.write_commit(&ci_result?)?;
+    fn diffstat(diff: &git2::Diff) -> Result<maud::Markup> {
+        Ok(html! {
+            @match unless_missing(diff.stats())? {
+                Some(stats) => pre {
+                    (String::from_utf8_lossy(&stats.to_buf(git2::DiffStatsFormat::FULL, 72)?))
+                },
+                None => "not available in this partial clone",
+            }
+        })
+    }
+
+    fn patches(diff: &git2::Diff) -> Result<maud::Markup> {
+        Ok(html! {
+            @for (delta_id, _delta) in diff.deltas().enumerate() {
+                @let patch = unless_missing(git2::Patch::from_diff(diff, delta_id))?;
+                @match patch {
+                    Some(Some(mut patch)) => {
+                        pre { (String::from_utf8_lossy(&patch.to_buf()?)) }
+                    }
+                    Some(None) => { "unchanged or binary" }
+                    None => { p { "Contents not available in this partial clone." } }
+                }
+            }
+        })
+    }
+
+    /// Links to the page of a commit when it gets one, otherwise shows `content` alone.
+    fn commit_link(
+        oid: git2::Oid,
+        pages: &std::collections::HashSet<git2::Oid>,
+        the_way_out: &UrlResolver,
+        content: maud::Markup,
+    ) -> maud::Markup {
+        html! {
+            @if pages.contains(&oid) {
+                a href=(the_way_out.commit_file(&oid.to_string())) { (content) }
+            } @else {
+                (content)
+            }
+        }
+    }
+
+    /// Lists commits with links to their pages.
+    fn commit_list(
+        &self,
+        log: &CommitLog,
+        pages: &std::collections::HashSet<git2::Oid>,
+        the_way_out: &UrlResolver,
+    ) -> Result<maud::Markup> {
+        Ok(html! {
+            h3 {
+                (log.oids.len())
+                @if log.oids.len() == 1 { " commit" } @else { " commits" }
+            }
+            ul {
+                @for oid in &log.oids {
+                    @let commit = self.repository.inner.find_commit(*oid)?;
+                    li {
+                        @let summary = String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default());
+                        (Self::commit_link(*oid, pages, the_way_out, html! { (summary) }))
+                        " "
+                        small { (String::from_utf8_lossy(commit.author().name_bytes())) }
+                    }
+                }
+            }
+            @if log.truncated {
+                p.omitted { "Older commits omitted." }
+            }
+        })
+    }
+
+    /// Selects commits that get their own pages: those presented in the log of HEAD or of any
+    /// selected branch.
+    fn commit_pages(&self) -> Result<std::collections::HashSet<git2::Oid>> {
+        let mut pages = std::collections::HashSet::new();
+        let branches = self.log_branches.iter().map(|branch| Some(branch.as_str()));
+        for branch in std::iter::once(None).chain(branches) {
+            pages.extend(self.commit_log_of(branch)?.oids);
+        }
+        Ok(pages)
+    }
+
+    pub fn write_all_commits(&self, pages: &std::collections::HashSet<git2::Oid>) -> Result<()> {
+        let containment = self.repository.containment(pages)?;
+        let oids: Vec<_> = pages.iter().copied().collect();
+        for ci_result in self.repository.commit_infos(&oids) {
+            self.write_commit(&ci_result?, pages, &containment)?;
+        }
+        Ok(())
+    }
+
+    fn write_tag(&self, tag: &TagInfo, pages: &std::collections::HashSet<git2::Oid>) -> Result<()> {
+        let tag_path = self.url.tag_file(&tag.name);
+        fs::create_dir_all(tag_path.base.parent().unwrap())?;
+        let the_way_out = self.url.rel_root_from(&tag_path);
+        let head_tree = self.repository.head_commit()?.map(|head| head.tree_id());
+        let content = self.template_page(
+            &format!("Tag {}", tag.name),
+            &tag_path.base,
+            html! {
+                dl {
+                    dt { "tag" }
+                    dd { (tag.name) }
+                    @match &tag.tag {
+                        Some(annotated) => {
+                            @if let Some(tagger) = annotated.tagger() {
+                                dt { "tagger" }
+                                dd itemscope itemtype="http://schema.org/Person" {
+                                    span itemprop="name" { (String::from_utf8_lossy(tagger.name_bytes())) }
+                                    " <"
+                                    @let email = String::from_utf8_lossy(tagger.email_bytes());
+                                    a itemprop="email" href={"mailto:" (&email)} { (email) }
+                                    ">"
+                                }
+                                dt { "date" }
+                                dd { (signature_time(&tagger.when())) }
+                            }
+                        }
+                        None => {
+                            dt { "kind" }
+                            dd { "lightweight tag" }
+                        }
+                    }
+                    dt { "target" }
+                    dd { (tag.target.kind().map(|kind| kind.str()).unwrap_or("object")) " " (tag.target.id()) }
+                    @if let Some(commit) = tag.target.as_commit() {
+                        dt { "commit" }
+                        dd {
+                            (Self::commit_link(commit.id(), pages, &the_way_out, html! { (commit.id()) }))
+                            " "
+                            (String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()))
+                        }
+                        dt { "tree" }
+                        dd {
+                            @if head_tree == Some(commit.tree_id()) {
+                                a href=(the_way_out.tree_index()) { (commit.tree_id()) }
+                            } @else {
+                                (commit.tree_id())
+                            }
+                        }
+                    }
+                    @if let Some(annotated) = &tag.tag {
+                        dt { "message" }
+                        dd {
+                            pre { (String::from_utf8_lossy(annotated.message_bytes().unwrap_or_default())) }
+                        }
+                    }
+                }
+            },
+        )?;
+        fs::write(tag_path.base, content.into_string().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn write_all_tags(&self, pages: &std::collections::HashSet<git2::Oid>) -> Result<()> {
+        for tag in self.repository.tags(self.tag_order)? {
+            self.write_tag(&tag, pages)?;
+        }
+        Ok(())
+    }
+
+    /// Gathers comparisons of consecutive releases and of the configured pairs of revisions.
+    fn comparisons<'r>(&'r self, releases: &[Release<'r>]) -> Result<Vec<Comparison<'r>>> {
+        let mut comparisons = Vec::new();
+        for release in releases {
+            if let Some((previous, previous_commit)) = &release.previous {
+                comparisons.push(Comparison {
+                    from: previous.clone(),
+                    to: release.tag.name.clone(),
+                    from_commit: previous_commit.clone(),
+                    to_commit: release.commit.clone(),
+                    commits: release.commits.clone(),
+                });
+            }
+        }
+        for (from, to) in &self.compare_pairs {
+            comparisons.push(self.repository.compare(from, to, &self.log_options)?);
+        }
+        Ok(comparisons)
+    }
+
+    fn write_comparison(
+        &self,
+        comparison: &Comparison,
+        pages: &std::collections::HashSet<git2::Oid>,
+    ) -> Result<()> {
+        let compare_path = self.url.compare_file(&comparison.from, &comparison.to);
+        fs::create_dir_all(compare_path.base.parent().unwrap())?;
+        let the_way_out = self.url.rel_root_from(&compare_path);
+        let diff = self
+            .repository
+            .diff_commits(Some(&comparison.from_commit), &comparison.to_commit)?;
+        let content = self.template_page(
+            &format!("Compare {}..{}", comparison.from, comparison.to),
+            &compare_path.base,
+            html! {
+                dl {
+                    dt { "from" }
+                    dd {
+                        (comparison.from) " "
+                        @let from = comparison.from_commit.id();
+                        (Self::commit_link(from, pages, &the_way_out, html! { (from) }))
+                    }
+                    dt { "to" }
+                    dd {
+                        (comparison.to) " "
+                        @let to = comparison.to_commit.id();
+                        (Self::commit_link(to, pages, &the_way_out, html! { (to) }))
+                    }
+                    dt { "diffstat" }
+                    dd { (Self::diffstat(&diff)?) }
+                }
+                (self.commit_list(&comparison.commits, pages, &the_way_out)?)
+                (Self::patches(&diff)?)
+            },
+        )?;
+        fs::write(compare_path.base, content.into_string().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn write_all_comparisons(
+        &self,
+        comparisons: &[Comparison],
+        pages: &std::collections::HashSet<git2::Oid>,
+    ) -> Result<()> {
+        for comparison in comparisons {
+            self.write_comparison(comparison, pages)?;
         }
         Ok(())
     }
 
-    pub fn write_tree_branch<'a, T: Iterator<Item = git2::TreeEntry<'a>>>(
+    fn write_releases(
         &self,
-        subtree: T,
+        releases: &[Release],
+        pages: &std::collections::HashSet<git2::Oid>,
+    ) -> Result<()> {
+        let releases_path = self.url.releases();
+        let the_way_out = self.url.rel_root_from(&releases_path);
+        let content = self.template_page(
+            "Releases",
+            &releases_path.base,
+            html! {
+                @if releases.is_empty() {
+                    p { "No releases yet." }
+                }
+                @for release in releases {
+                    section.release id=(release.tag.name) {
+                        h2 { a href=(the_way_out.tag_file(&release.tag.name)) { (release.tag.name) } }
+                        dl {
+                            dt { "date" }
+                            @let tagger = release.tag.tag.as_ref().and_then(|tag| tag.tagger());
+                            @match tagger {
+                                Some(tagger) => dd { (signature_time(&tagger.when()).format("%Y-%m-%d")) },
+                                None => dd { (commit_time(&release.commit).format("%Y-%m-%d")) },
+                            }
+                            dt { "previous release" }
+                            @match &release.previous {
+                                Some((name, _)) => dd {
+                                    a href={"#" (percent_encoding::utf8_percent_encode(name, PATH_SEGMENT))} { (name) }
+                                    " ("
+                                    a href=(the_way_out.compare_file(name, &release.tag.name)) { "full diff" }
+                                    ")"
+                                },
+                                None => dd { "none" },
+                            }
+                            @if let Some(message) = release.tag.tag.as_ref().and_then(|tag| tag.message_bytes()) {
+                                dt { "message" }
+                                dd { pre { (String::from_utf8_lossy(message)) } }
+                            }
+                            dt { "diffstat" }
+                            dd {
+                                @let previous = release.previous.as_ref().map(|(_, commit)| commit);
+                                (Self::diffstat(&self.repository.diff_commits(previous, &release.commit)?)?)
+                            }
+                        }
+                        (self.commit_list(&release.commits, pages, &the_way_out)?)
+                    }
+                }
+            },
+        )?;
+        fs::write(releases_path.base, content.into_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// Time before which the last commit of a branch has to be made for the branch to be stale.
+    fn stale_cutoff(&self) -> chrono::DateTime<chrono::Utc> {
+        let now = chrono::Utc::now();
+        chrono::Duration::from_std(self.stale_after)
+            .ok()
+            .and_then(|age| now.checked_sub_signed(age))
+            .unwrap_or(chrono::MIN_DATETIME)
+    }
+
+    /// Tells whether a branch tip other than the head commit is older than the configured age.
+    fn is_stale(&self, tip: &git2::Commit) -> Result<bool> {
+        let head = self.repository.head_commit()?.map(|head| head.id());
+        Ok(head != Some(tip.id()) && tip.time().seconds() < self.stale_cutoff().timestamp())
+    }
+
+    fn write_refs(&self, pages: &std::collections::HashSet<git2::Oid>) -> Result<()> {
+        let refs_path = self.url.refs_list();
+        let the_way_out = self.url.rel_root_from(&refs_path);
+        let mut stale = Vec::new();
+        for (name, commit) in self.repository.branches()? {
+            if self.is_stale(&commit)? {
+                stale.push((name, commit));
+            }
+        }
+        let content = self.template_page(
+            "Branches and tags",
+            &refs_path.base,
+            html! {
+                h2 { "Branches" }
+                @let head = self.repository.head_commit()?.map(|head| head.id());
+                @let base = self.repository.default_branch_tip()?;
+                @let base_name = base.as_ref().map_or("HEAD", |(name, _)| name.as_str());
+                table {
+                    thead {
+                        tr {
+                            th { "Name" }
+                            th { "Last commit" }
+                            th { "Date" }
+                            th { "Author" }
+                            th.numeric title={"Commits not on " (base_name)} { "Ahead of " (base_name) }
+                            th.numeric title={"Commits of " (base_name) " missing here"} { "Behind " (base_name) }
+                            th { "Merge base with " (base_name) }
+                        }
+                    }
+                    tbody {
+                        @for (name, commit) in self.repository.branches()? {
+                            tr {
+                                @let divergence = match &base {
+                                    Some((_, base)) => self.repository.divergence(&commit, base)?,
+                                    None => None,
+                                };
+                                @let is_base = base.as_ref().is_some_and(|(_, base)| base.id() == commit.id());
+                                td {
+                                    @if self.log_branches.contains(&name) {
+                                        a href=(the_way_out.log_page(Some(&name), 1)) { (name) }
+                                    } @else {
+                                        (name)
+                                    }
+                                    @if head == Some(commit.id()) {
+                                        " " small.head { "(HEAD)" }
+                                    }
+                                    @if !is_base && divergence.as_ref().is_some_and(|divergence| divergence.ahead == 0) {
+                                        " " small.merged { "(merged)" }
+                                    }
+                                    @if self.is_stale(&commit)? {
+                                        " " small.stale { "(stale)" }
+                                    }
+                                }
+                                td { (String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())) }
+                                td { (commit_time(&commit).format("%Y-%m-%d")) }
+                                td { (String::from_utf8_lossy(commit.author().name_bytes())) }
+                                @match divergence {
+                                    Some(divergence) => {
+                                        td.numeric { (divergence.ahead) }
+                                        td.numeric { (divergence.behind) }
+                                        td {
+                                            @match divergence.merge_base {
+                                                Some(merge_base) => {
+                                                    @let short_id = html! { code { (merge_base.to_string()[..7]) } };
+                                                    (Self::commit_link(merge_base, pages, &the_way_out, short_id))
+                                                },
+                                                None => "none",
+                                            }
+                                        }
+                                    }
+                                    None => td.numeric colspan="3" { "?" },
+                                }
+                            }
+                        }
+                    }
+                }
+                @if !stale.is_empty() {
+                    h2 { "Stale branches" }
+                    p { "Branches other than HEAD with no commits since " (self.stale_cutoff().format("%Y-%m-%d")) "." }
+                    table {
+                        thead {
+                            tr {
+                                th { "Name" }
+                                th { "Last commit" }
+                                th { "Date" }
+                                th { "Committer" }
+                            }
+                        }
+                        tbody {
+                            @for (name, commit) in &stale {
+                                tr {
+                                    td { (name) }
+                                    td { (String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())) }
+                                    td { (commit_time(commit).format("%Y-%m-%d")) }
+                                    td itemscope itemtype="http://schema.org/Person" {
+                                        @let sig = commit.committer();
+                                        span itemprop="name" { (String::from_utf8_lossy(sig.name_bytes())) }
+                                        " <"
+                                        @let email = String::from_utf8_lossy(sig.email_bytes());
+                                        a itemprop="email" href={"mailto:" (&email)} { (email) }
+                                        ">"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                h2 { "Tags" }
+                table {
+                    thead {
+                        tr {
+                            th { "Name" }
+                            th { "Commit" }
+                            th { "Date" }
+                        }
+                    }
+                    tbody {
+                        @for tag in self.repository.tags(self.tag_order)? {
+                            tr {
+                                td { a href=(the_way_out.tag_file(&tag.name)) { (tag.name) } }
+                                @match tag.target.as_commit() {
+                                    Some(commit) => {
+                                        td { (String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())) }
+                                        td { (commit_time(commit).format("%Y-%m-%d")) }
+                                    }
+                                    None => {
+                                        td { (tag.target.kind().map(|kind| kind.str()).unwrap_or("object")) " " (tag.target.id()) }
+                                        td {}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                @if !self.compare_pairs.is_empty() {
+                    h2 { "Comparisons" }
+                    ul {
+                        @for (from, to) in &self.compare_pairs {
+                            li { a href=(the_way_out.compare_file(from, to)) { (from) ".." (to) } }
+                        }
+                    }
+                }
+            },
+        )?;
+        fs::write(refs_path.base, content.into_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// Finds a README among the entries of a tree, regardless of its extension.
+    /// Markdown READMEs are preferred over others, like forges do.
+    fn find_readme<'a>(entries: &[git2::TreeEntry<'a>]) -> Option<git2::TreeEntry<'a>> {
+        entries
+            .iter()
+            .filter(|entry| entry.kind() == Some(git2::ObjectType::Blob))
+            .filter(|entry| entry.filemode() != FILEMODE_SYMLINK)
+            .filter(|entry| {
+                entry
+                    .name_bytes()
+                    .split(|&c| c == b'.')
+                    .next()
+                    .is_some_and(|stem| stem.eq_ignore_ascii_case(b"readme"))
+            })
+            .min_by_key(|entry| {
+                !is_markdown(std::path::Path::new(std::ffi::OsStr::from_bytes(
+                    entry.name_bytes(),
+                )))
+            })
+            .cloned()
+    }
+
+    /// Renders a README found at `readme_path`, as HTML if it is written in Markdown and as
+    /// plain text otherwise. Raw HTML is shown escaped, links and images with schemes other than
+    /// `http`, `https` and `mailto` are dropped, and relative ones that point into the tree are
+    /// redirected to their pages and raw files.
+    fn readme<'t>(
+        &self,
+        root: &git2::Tree,
+        readme_path: &std::path::Path,
+        text: &'t str,
+        the_way_out: &UrlResolver,
+    ) -> maud::Markup {
+        if !is_markdown(readme_path) {
+            return html! { pre { (text) } };
+        }
+        use pulldown_cmark::{Event, Tag};
+        let relink = |dest: pulldown_cmark::CowStr<'t>, raw: bool| -> pulldown_cmark::CowStr<'t> {
+            if dest.starts_with('#') || dest.starts_with('/') {
+                return dest;
+            }
+            // Browsers ignore tabs and newlines anywhere in a URL and control characters
+            // around it, so those must not hide a scheme from the check below.
+            let cleaned: String = dest
+                .trim_matches(|c: char| c <= ' ')
+                .chars()
+                .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+                .collect();
+            let before_path = &cleaned[..cleaned.find(['/', '?', '#']).unwrap_or(cleaned.len())];
+            if let Some((scheme, _)) = before_path.split_once(':') {
+                return if ["http", "https", "mailto"]
+                    .iter()
+                    .any(|safe| scheme.eq_ignore_ascii_case(safe))
+                {
+                    dest
+                } else {
+                    "".into()
+                };
+            }
+            let (path, fragment) = dest.split_at(dest.find('#').unwrap_or(dest.len()));
+            let path = percent_encoding::percent_decode_str(path).collect::<Vec<u8>>();
+            let target = std::path::Path::new(std::ffi::OsStr::from_bytes(&path));
+            match self.repository.resolve_symlink(root, readme_path, target) {
+                SymlinkTarget::Inside(resolved) => {
+                    let output_path = self.tree_output_path(root, &resolved);
+                    if raw {
+                        self.raw_file(the_way_out, output_path).to_string().into()
+                    } else {
+                        format!("{}{}", the_way_out.tree_node(output_path), fragment).into()
+                    }
+                }
+                _ => dest,
+            }
+        };
+        let parser =
+            pulldown_cmark::Parser::new_ext(text, pulldown_cmark::Options::all()).map(|event| {
+                match event {
+                    Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+                    Event::Start(Tag::Link {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    }) => Event::Start(Tag::Link {
+                        link_type,
+                        dest_url: relink(dest_url, false),
+                        title,
+                        id,
+                    }),
+                    Event::Start(Tag::Image {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    }) => Event::Start(Tag::Image {
+                        link_type,
+                        dest_url: relink(dest_url, true),
+                        title,
+                        id,
+                    }),
+                    event => event,
+                }
+            });
+        let mut rendered = String::new();
+        pulldown_cmark::html::push_html(&mut rendered, parser);
+        maud::PreEscaped(rendered)
+    }
+
+    /// Describes where a symbolic link points to, linking the target if it is in the tree.
+    fn symlink_target(
+        &self,
+        root: &git2::Tree,
+        link_path: &std::path::Path,
+        blob: &git2::Blob,
+        the_way_out: &UrlResolver,
+    ) -> Result<maud::Markup> {
+        let target = std::path::Path::new(std::ffi::OsStr::from_bytes(blob.content()));
+        let target_str = target.to_string_lossy();
+        Ok(
+            match self.repository.resolve_symlink(root, link_path, target) {
+                SymlinkTarget::Inside(resolved) => html! {
+                    a href=(the_way_out.tree_node(self.tree_output_path(root, &resolved))) { (target_str) }
+                },
+                SymlinkTarget::Broken => html! {
+                    span.broken { (target_str) } " (broken)"
+                },
+                SymlinkTarget::OutOfTree => html! {
+                    span.broken { (target_str) } " (outside of the tree)"
+                },
+            },
+        )
+    }
+
+    /// Root of rustagit pages generated for the repository behind a submodule, if configured.
+    fn submodule_pages(
+        &self,
+        submodule: &Submodule,
+        the_way_out: &UrlResolver,
+    ) -> Option<UrlResolver> {
+        let prefix = self.submodule_pages.as_ref()?;
+        let base = if prefix.contains("://") {
+            UrlResolver::new(std::path::PathBuf::from(prefix))
+        } else {
+            the_way_out.join(prefix)
+        };
+        Some(base.join(submodule.repository_name()))
+    }
+
+    /// Describes a submodule pinned at `path` to commit `id`, linking its remote where possible.
+    fn submodule(
+        &self,
+        submodules: &[Submodule],
+        path: &std::path::Path,
+        id: git2::Oid,
+        the_way_out: &UrlResolver,
+    ) -> Result<maud::Markup> {
+        let path_str = path.to_string_lossy();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let submodule = submodules
+            .iter()
+            .find(|submodule| submodule.path == path_str);
+        let pages = submodule.and_then(|submodule| self.submodule_pages(submodule, the_way_out));
+        Ok(html! {
+            span.submodule {
+                @match &pages {
+                    Some(pages) => a href=(pages.tree_index()) { (name) },
+                    None => (name),
+                }
+                " @ "
+                @match &pages {
+                    Some(pages) => a href=(pages.commit_file(&id.to_string())) { code { (id) } },
+                    None => code { (id) },
+                }
+                @if let Some(url) = submodule.and_then(|submodule| submodule.url.as_ref()) {
+                    " ("
+                    @if url.starts_with("http://") || url.starts_with("https://") {
+                        a href=(url) { (url) }
+                    } @else {
+                        (url)
+                    }
+                    ")"
+                }
+            }
+        })
+    }
+
+    pub fn write_tree_branch(
+        &self,
+        root: &git2::Tree,
+        subtree: git2::Tree,
         file_path: UrlResolver,
         tree_path: std::path::PathBuf,
     ) -> Result<()> {
@@ -306,21 +1342,55 @@ impl Templator<'_> {
         let subtree_rel = UrlResolver {
             base: std::path::PathBuf::from(subtree_root.file_name().unwrap()),
         };
+        let entries: Vec<_> = subtree.iter().collect();
+        let names = output_names(&subtree);
+        let readme = Self::find_readme(&entries);
+        let submodules = self.repository.submodules(root);
+        let the_way_out = self.url.rel_root_from(&file_path);
         let content = self.template_page(
-            tree_path.to_str().ok_or(InvalidUtf)?,
+            &tree_path.to_string_lossy(),
             &file_path,
             html! {
                 ul {
-                    @for item in subtree {
+                    @for (item, output_name) in entries.iter().zip(&names) {
                         li {
-                            @let name = item.name().ok_or(InvalidUtf)?;
-                            a href=(subtree_rel.join(name).dot_html()) {
-                                @if let Some(git2::ObjectType::Tree) = item.kind() {
-                                    (name) "/"
-                                } @else {
-                                    (name)
+                            @let file_name = safe_file_name(item.name_bytes());
+                            @let name = String::from_utf8_lossy(item.name_bytes());
+                            @if let Some(git2::ObjectType::Commit) = item.kind() {
+                                @let path = tree_path.join(&file_name);
+                                (self.submodule(&submodules, path.strip_prefix("/")?, item.id(), &the_way_out)?)
+                            } @else {
+                                a href=(subtree_rel.join(output_name).dot_html()) {
+                                    @if let Some(git2::ObjectType::Tree) = item.kind() {
+                                        (name) "/"
+                                    } @else {
+                                        (name)
+                                    }
+                                }
+                            }
+                            @if item.filemode() == FILEMODE_SYMLINK {
+                                " -> "
+                                @match unless_missing(item.to_object(&self.repository.inner).and_then(|object| object.peel_to_blob()))? {
+                                    Some(blob) => (self.symlink_target(root, tree_path.join(&file_name).strip_prefix("/")?, &blob, &the_way_out)?),
+                                    None => "?",
+                                }
+                            }
+                        }
+                    }
+                }
+                @if let Some(readme) = readme {
+                    @let file_name = safe_file_name(readme.name_bytes());
+                    @let readme_path = tree_path.join(&file_name);
+                    @let readme_rel = readme_path.strip_prefix("/")?;
+                    @let blob = unless_missing(readme.to_object(&self.repository.inner).and_then(|object| object.peel_to_blob()))?;
+                    @if let Some((text, _)) = blob.as_ref().and_then(|blob| self.decode_blob(root, readme_rel, blob.content())) {
+                        article.readme {
+                            h2 {
+                                a href=(the_way_out.tree_node(self.tree_output_path(root, readme_rel))) {
+                                    (String::from_utf8_lossy(readme.name_bytes()))
                                 }
                             }
+                            (self.readme(root, readme_rel, &text, &the_way_out))
                         }
                     }
                 }
@@ -335,12 +1405,7 @@ impl Templator<'_> {
         output_path: P,
         content: &str,
     ) -> Result<maud::Markup> {
-        let file_name = output_path
-            .as_ref()
-            .file_name()
-            .unwrap()
-            .to_str()
-            .ok_or(InvalidUtf)?;
+        let file_name = output_path.as_ref().file_name().unwrap().to_string_lossy();
         let name_syntax = self.syntax_set.find_syntax_by_extension(&file_name);
         let ext_syntax = self.syntax_set.find_syntax_by_extension(
             output_path
@@ -383,29 +1448,105 @@ impl Templator<'_> {
         })
     }
 
+    /// Exact copy of a blob found at `path` relative to the root of the tree.
+    fn raw_file<P: AsRef<std::path::Path>>(&self, root: &UrlResolver, path: P) -> UrlResolver {
+        if self.raw_dir {
+            root.raw_dir().join(path)
+        } else {
+            root.tree_dir().join(path)
+        }
+    }
+
+    /// Decodes a blob as text. Git stores files with `working-tree-encoding` as UTF-8 already,
+    /// so the attribute is only consulted for blobs committed before it was set.
+    /// Otherwise legacy encodings are guessed. Returns None for content that looks binary.
+    fn decode_blob<'b>(
+        &self,
+        root: &git2::Tree,
+        tree_path: &std::path::Path,
+        content: &'b [u8],
+    ) -> Option<(std::borrow::Cow<'b, str>, &'static encoding_rs::Encoding)> {
+        if let Ok(text) = std::str::from_utf8(content) {
+            return Some((text.into(), encoding_rs::UTF_8));
+        }
+        let declared = self
+            .repository
+            .attribute(root, tree_path, "working-tree-encoding")
+            .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()));
+        let encoding =
+            match declared.or_else(|| encoding_rs::Encoding::for_bom(content).map(|(e, _)| e)) {
+                Some(encoding) => encoding,
+                None if content.contains(&0) || Media::detect(content).is_some() => return None,
+                None => {
+                    let mut detector = chardetng::EncodingDetector::new();
+                    detector.feed(content, true);
+                    detector.guess(None, true)
+                }
+            };
+        match encoding.decode(content) {
+            (text, encoding, false) => Some((text, encoding)),
+            (_, _, true) => None,
+        }
+    }
+
     pub fn write_tree_leaf(
         &self,
+        root: &git2::Tree,
         object: git2::Blob,
         file_path: UrlResolver,
         tree_path: std::path::PathBuf,
     ) -> Result<()> {
-        let raw_name = file_path.base.with_extension("");
-        let tree_path_str = tree_path.to_str().ok_or(InvalidUtf)?;
-        let content = self.template_page(tree_path_str, &file_path, match std::str::from_utf8(object.content()) {
-            Ok(content) => {
+        let tree_path_str = tree_path.to_string_lossy();
+        let output_path = file_path
+            .base
+            .strip_prefix(&self.url.tree_dir().base)?
+            .with_extension("");
+        let raw_path = self.raw_file(&self.url, &output_path);
+        fs::create_dir_all(raw_path.base.parent().unwrap())?;
+        fs::write(&raw_path, object.content())?;
+        let raw_href = self.raw_file(&self.url.rel_root_from(&file_path), &output_path);
+        let size = human_size(object.size());
+        let decoded = self.decode_blob(root, tree_path.strip_prefix("/")?, object.content());
+        let content = self.template_page(&tree_path_str, &file_path, match decoded {
+            Some((content, encoding)) => {
                 html! {
+                    p {
+                        a href=(raw_href) { "Raw" } " (" (size)
+                        @if encoding != encoding_rs::UTF_8 {
+                            ", decoded from " (encoding.name())
+                        }
+                        ")"
+                    }
                     span itemscope itemtype="http://schema.org/TextDigitalDocument" {
                         link itemprop="targetCollection" itemid="#repository";
                         meta itemprop="name" content=(tree_path_str);
-                        (self.highlight_object(raw_name, content)?)
+                        (self.highlight_object(&tree_path, &content)?)
                     }
                 }
             },
-            Err(_) => {
-                fs::write(&raw_name, object.content())?;
-                html! {
-                    p { "This is not a file of UTF-8 honour." }
-                    a href=(raw_name.file_name().unwrap().to_str().ok_or(InvalidUtf)?) { "See raw" }
+            None => {
+                match Media::detect(object.content()) {
+                    Some(media) => html! {
+                        figure {
+                            @match media.kind {
+                                MediaKind::Image => img src=(&raw_href) alt=(tree_path_str);,
+                                MediaKind::Audio => audio controls { source src=(&raw_href) type=(media.mime); },
+                                MediaKind::Video => video controls { source src=(&raw_href) type=(media.mime); },
+                            }
+                            figcaption {
+                                (media.mime) ", "
+                                @if let Ok(dimensions) = imagesize::blob_size(object.content()) {
+                                    (dimensions.width) "×" (dimensions.height) " px, "
+                                }
+                                (size)
+                            }
+                        }
+                        a href=(&raw_href) { "See raw" }
+                    },
+                    None => html! {
+                        p { "This is not a file of UTF-8 honour." }
+                        a href=(&raw_href) { "See raw" } " (" (size) ")"
+                    },
                 }
             },
         })?;
@@ -413,62 +1554,467 @@ impl Templator<'_> {
         Ok(())
     }
 
-    pub fn write_all_tree_nodes(&self) -> Result<()> {
-        let head = self.repository.inner.head()?;
-        let head_tree = head.peel_to_tree()?;
-        let mut err = Ok(());
-        let tree_root = self.url.tree_dir();
-        let slash_root = std::path::PathBuf::from("/");
-        let walker = |parent: &str, entry: &git2::TreeEntry| -> Result<()> {
-            let output_path = if !parent.is_empty() {
-                tree_root.join(parent)
-            } else {
-                tree_root.clone()
-            }
-            .join(entry.name().ok_or(InvalidUtf)?)
-            .dot_html();
-            let subtree_path = if !parent.is_empty() {
-                slash_root.join(parent)
-            } else {
-                slash_root.clone()
+    pub fn write_tree_symlink(
+        &self,
+        root: &git2::Tree,
+        object: git2::Blob,
+        file_path: UrlResolver,
+        tree_path: std::path::PathBuf,
+    ) -> Result<()> {
+        let tree_path_str = tree_path.to_string_lossy();
+        let the_way_out = self.url.rel_root_from(&file_path);
+        let content = self.template_page(
+            &tree_path_str,
+            &file_path,
+            html! {
+                p {
+                    "Symbolic link: "
+                    (tree_path.file_name().unwrap_or_default().to_string_lossy())
+                    " -> "
+                    (self.symlink_target(root, tree_path.strip_prefix("/")?, &object, &the_way_out)?)
+                }
+            },
+        )?;
+        fs::write(file_path.base, content.into_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes a placeholder for a blob that was not fetched into a partial clone.
+    pub fn write_tree_missing(
+        &self,
+        file_path: UrlResolver,
+        tree_path: std::path::PathBuf,
+    ) -> Result<()> {
+        let content = self.template_page(
+            &tree_path.to_string_lossy(),
+            &file_path,
+            html! {
+                p { "Contents of this file are not available in this partial clone." }
+            },
+        )?;
+        fs::write(file_path.base, content.into_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// Maps a path relative to the root of the tree onto the path its pages are written at,
+    /// accounting for names disambiguated by `output_names`.
+    fn tree_output_path(&self, root: &git2::Tree, path: &std::path::Path) -> std::path::PathBuf {
+        let mut tree = Some(root.clone());
+        let mut output_path = std::path::PathBuf::new();
+        for component in path.components() {
+            let name = component.as_os_str();
+            let found = tree.as_ref().and_then(|tree| {
+                let index = tree
+                    .iter()
+                    .position(|entry| safe_file_name(entry.name_bytes()) == name)?;
+                Some((
+                    tree.get(index)?.to_owned(),
+                    output_names(tree).swap_remove(index),
+                ))
+            });
+            match found {
+                Some((entry, output_name)) => {
+                    output_path.push(output_name);
+                    tree = entry
+                        .to_object(&self.repository.inner)
+                        .and_then(|object| object.peel_to_tree())
+                        .ok();
+                }
+                None => {
+                    output_path.push(name);
+                    tree = None;
+                }
             }
-            .join(entry.name().ok_or(InvalidUtf)?);
+        }
+        output_path
+    }
+
+    /// Writes pages for all entries of `tree`, found at `tree_path` and written into
+    /// `output_dir`, and everything below them.
+    fn write_tree_nodes(
+        &self,
+        root: &git2::Tree,
+        tree: &git2::Tree,
+        tree_path: &std::path::Path,
+        output_dir: &std::path::Path,
+    ) -> Result<()> {
+        for (entry, output_name) in tree.iter().zip(output_names(tree)) {
+            let subtree_path = tree_path.join(safe_file_name(entry.name_bytes()));
+            let subtree_output_dir = output_dir.join(output_name);
+            let output_path = self.url.tree_file(&subtree_output_dir);
             match entry.kind() {
                 Some(git2::ObjectType::Tree) => {
                     let subtree = entry.to_object(&self.repository.inner)?.peel_to_tree()?;
-                    self.write_tree_branch(subtree.into_iter(), output_path, subtree_path)?;
+                    self.write_tree_branch(
+                        root,
+                        subtree.clone(),
+                        output_path,
+                        subtree_path.clone(),
+                    )?;
+                    self.write_tree_nodes(root, &subtree, &subtree_path, &subtree_output_dir)?;
                 }
                 Some(git2::ObjectType::Blob) => {
-                    let obj = entry.to_object(&self.repository.inner)?.peel_to_blob()?;
-                    self.write_tree_leaf(obj, output_path, subtree_path)?;
+                    let blob = entry
+                        .to_object(&self.repository.inner)
+                        .and_then(|object| object.peel_to_blob());
+                    match unless_missing(blob)? {
+                        Some(obj) if entry.filemode() == FILEMODE_SYMLINK => {
+                            self.write_tree_symlink(root, obj, output_path, subtree_path)?
+                        }
+                        Some(obj) => self.write_tree_leaf(root, obj, output_path, subtree_path)?,
+                        None => self.write_tree_missing(output_path, subtree_path)?,
+                    }
                 }
                 _ => {}
             }
-            Ok(())
+        }
+        Ok(())
+    }
+
+    pub fn write_all_tree_nodes(&self) -> Result<()> {
+        let head_tree = match self.repository.head_commit()? {
+            Some(head) => head.tree()?,
+            None => {
+                let tree_index = self.url.tree_index();
+                let content = self.template_page(
+                    "/",
+                    &tree_index,
+                    html! {
+                        p { "No commits yet." }
+                    },
+                )?;
+                fs::write(tree_index.base, content.into_string().as_bytes())?;
+                return Ok(());
+            }
         };
+        let slash_root = std::path::PathBuf::from("/");
         self.write_tree_branch(
-            head_tree.into_iter(),
-            tree_root.dot_html(),
-            std::path::PathBuf::from("/"),
+            &head_tree,
+            head_tree.clone(),
+            self.url.tree_index(),
+            slash_root.clone(),
         )?;
-        head_tree.walk(git2::TreeWalkMode::PreOrder, |parent, entry| {
-            match walker(parent, entry) {
-                Ok(()) => git2::TreeWalkResult::Ok,
-                Err(e) => {
-                    err = Err(e);
-                    git2::TreeWalkResult::Abort
-                }
-            }
-        })?;
-        err
+        self.write_tree_nodes(
+            &head_tree,
+            &head_tree,
+            &slash_root,
+            std::path::Path::new(""),
+        )
     }
 
     pub fn generate(&self) -> Result<()> {
         self.precreate_dirs()?;
         self.write_default_css_if_not_exists()?;
-        self.write_commit_log()?;
-        self.write_all_commits()?;
+        self.write_commit_log(None)?;
+        for branch in &self.log_branches {
+            self.write_commit_log(Some(branch))?;
+        }
+        let pages = self.commit_pages()?;
+        self.write_all_commits(&pages)?;
+        self.write_all_tags(&pages)?;
+        self.write_refs(&pages)?;
+        let releases = self
+            .repository
+            .releases(self.tag_order, &self.log_options)?;
+        self.write_releases(&releases, &pages)?;
+        let comparisons = self.comparisons(&releases)?;
+        self.write_all_comparisons(&comparisons, &pages)?;
         self.write_all_tree_nodes()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::path::PathBuf;
+
+    fn href<P: AsRef<std::path::Path>>(path: P) -> String {
+        UrlResolver::new(PathBuf::from("."))
+            .tree_file(path)
+            .to_string()
+    }
+
+    #[test]
+    fn plain_names_are_kept() {
+        assert_eq!(href("src/main.rs"), "./tree/src/main.rs.html");
+    }
+
+    #[test]
+    fn reserved_characters_are_escaped() {
+        assert_eq!(href("C#/a?b.txt"), "./tree/C%23/a%3Fb.txt.html");
+        assert_eq!(href("100%.md"), "./tree/100%25.md.html");
+        assert_eq!(href("my file"), "./tree/my%20file.html");
+    }
+
+    #[test]
+    fn non_ascii_names_are_escaped() {
+        assert_eq!(
+            href("zażółć.txt"),
+            "./tree/za%C5%BC%C3%B3%C5%82%C4%87.txt.html"
+        );
+        assert_eq!(href(OsStr::from_bytes(b"na\xefve")), "./tree/na%EFve.html");
+    }
+
+    #[test]
+    fn filesystem_paths_stay_raw() {
+        let url = UrlResolver::new(PathBuf::from("/out")).tree_file("a b#c");
+        assert_eq!(url.as_ref(), std::path::Path::new("/out/tree/a b#c.html"));
+    }
+
+    /// Creates an empty bare repository in a temporary directory of its own, as tests run in
+    /// parallel.
+    fn scratch_repository(name: &str) -> PathBuf {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!(
+            "rustagit-{}-{}-{}",
+            name,
+            std::process::id(),
+            count
+        ));
+        git2::Repository::init_bare(&path).unwrap();
+        path
+    }
+
+    /// Writes a tree with an empty blob for each file and an empty-file subtree for each
+    /// directory (names ending with `/`).
+    fn write_tree(repository: &git2::Repository, names: &[&str]) -> git2::Oid {
+        let blob = repository.blob(b"").unwrap();
+        let subtree = {
+            let mut builder = repository.treebuilder(None).unwrap();
+            builder.insert("file", blob, 0o100644).unwrap();
+            builder.write().unwrap()
+        };
+        let mut builder = repository.treebuilder(None).unwrap();
+        for name in names {
+            match name.strip_suffix('/') {
+                Some(dir) => builder.insert(dir, subtree, 0o040000).unwrap(),
+                None => builder.insert(name, blob, 0o100644).unwrap(),
+            };
+        }
+        builder.write().unwrap()
+    }
+
+    fn names_in(names: &[&str]) -> Vec<String> {
+        let path = scratch_repository("output-names");
+        let repository = git2::Repository::open(&path).unwrap();
+        let tree = repository
+            .find_tree(write_tree(&repository, names))
+            .unwrap();
+        let output = output_names(&tree)
+            .into_iter()
+            .map(|name| name.into_string().unwrap())
+            .collect();
+        std::fs::remove_dir_all(&path).unwrap();
+        output
+    }
+
+    #[test]
+    fn distinct_names_are_kept() {
+        assert_eq!(names_in(&["a.rs", "b/", "c"]), ["a.rs", "b", "c"]);
+    }
+
+    #[test]
+    fn names_differing_in_case_get_suffixes() {
+        assert_eq!(names_in(&["README", "readme"]), ["README", "readme~1"]);
+        assert_eq!(
+            names_in(&["README.md", "Readme.md", "readme.md"]),
+            ["README.md", "Readme~1.md", "readme~2.md"]
+        );
+    }
+
+    #[test]
+    fn names_clashing_with_pages_get_suffixes() {
+        assert_eq!(names_in(&["Foo", "Foo.html"]), ["Foo", "Foo~1.html"]);
+        assert_eq!(names_in(&["docs/", "docs.html"]), ["docs.html", "docs~1"]);
+    }
+
+    /// Sets up a templator with default options for the repository at `path`.
+    fn templator<'a>(
+        path: &std::path::Path,
+        theme: &'a syntect::highlighting::Theme,
+    ) -> Templator<'a> {
+        Templator {
+            repository: Repository::open(path, None).unwrap(),
+            url: UrlResolver::new(PathBuf::from(".")),
+            syntax_set: syntect::parsing::SyntaxSet::new(),
+            theme,
+            submodule_pages: None,
+            raw_dir: false,
+            log_options: LogOptions::default(),
+            log_page_size: None,
+            log_branches: Vec::new(),
+            tag_order: TagOrder::default(),
+            compare_pairs: Vec::new(),
+            stale_after: std::time::Duration::from_secs(0),
+            notes_refs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn output_paths_follow_output_names() {
+        let path = scratch_repository("output-paths");
+        let inner = git2::Repository::open(&path).unwrap();
+        let tree = inner
+            .find_tree(write_tree(
+                &inner,
+                &["docs/", "docs.html", "README", "readme"],
+            ))
+            .unwrap();
+        let theme = syntect::highlighting::Theme::default();
+        let templator = templator(&path, &theme);
+        let output_path = |path: &str| {
+            templator
+                .tree_output_path(&tree, std::path::Path::new(path))
+                .into_os_string()
+                .into_string()
+                .unwrap()
+        };
+        assert_eq!(output_path("docs.html"), "docs.html");
+        assert_eq!(output_path("docs/file"), "docs~1/file");
+        assert_eq!(output_path("README"), "README");
+        assert_eq!(output_path("readme"), "readme~1");
+        assert_eq!(output_path("missing/file"), "missing/file");
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn absolute_urls_keep_their_scheme() {
+        let url = UrlResolver::new(PathBuf::from("https://example.com/repos")).join("x y");
+        assert_eq!(url.to_string(), "https://example.com/repos/x%20y");
+    }
+
+    fn render_readme(markdown: &str) -> String {
+        let path = scratch_repository("readme");
+        let inner = git2::Repository::open(&path).unwrap();
+        let tree = inner
+            .find_tree(write_tree(
+                &inner,
+                &["docs/", "A.png", "a.png", "README.md"],
+            ))
+            .unwrap();
+        let theme = syntect::highlighting::Theme::default();
+        let rendered = templator(&path, &theme)
+            .readme(
+                &tree,
+                std::path::Path::new("docs/README.md"),
+                markdown,
+                &UrlResolver::new(PathBuf::from("..")),
+            )
+            .into_string();
+        std::fs::remove_dir_all(&path).unwrap();
+        rendered
+    }
+
+    #[test]
+    fn readme_links_point_to_pages_and_raw_copies() {
+        assert_eq!(
+            render_readme("[up](../README.md#usage) [web](https://example.com) [me](#top)"),
+            "<p><a href=\"../tree/README.md.html#usage\">up</a> \
+             <a href=\"https://example.com\">web</a> <a href=\"#top\">me</a></p>\n"
+        );
+        assert_eq!(
+            render_readme("![big](../A.png) ![small](../a.png)"),
+            "<p><img src=\"../tree/A.png\" alt=\"big\" /> \
+             <img src=\"../tree/a~1.png\" alt=\"small\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn readme_links_with_unsafe_schemes_are_dropped() {
+        for link in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "<java\tscript:alert(1)>",
+            "data:text/html,x",
+            "vbscript:msgbox",
+        ] {
+            let rendered = render_readme(&format!("[a]({}) ![b]({})", link, link));
+            assert_eq!(
+                rendered, "<p><a href=\"\">a</a> <img src=\"\" alt=\"b\" /></p>\n",
+                "{}",
+                link
+            );
+        }
+        assert_eq!(
+            render_readme("[mail](mailto:a@example.com) <b>bold</b>"),
+            "<p><a href=\"mailto:a@example.com\">mail</a> &lt;b&gt;bold&lt;/b&gt;</p>\n"
+        );
+    }
+
+    fn readme_in(names: &[&str]) -> Option<String> {
+        let path = scratch_repository("find-readme");
+        let repository = git2::Repository::open(&path).unwrap();
+        let tree = repository
+            .find_tree(write_tree(&repository, names))
+            .unwrap();
+        let entries: Vec<_> = tree.iter().collect();
+        let readme = Templator::find_readme(&entries).map(|entry| entry.name().unwrap().to_owned());
+        std::fs::remove_dir_all(&path).unwrap();
+        readme
+    }
+
+    #[test]
+    fn markdown_readmes_are_preferred() {
+        assert_eq!(
+            readme_in(&["README", "README.md", "README.txt"]).as_deref(),
+            Some("README.md")
+        );
+        assert_eq!(
+            readme_in(&["README", "readme.txt"]).as_deref(),
+            Some("README")
+        );
+        assert_eq!(readme_in(&["README/", "main.rs"]), None);
+    }
+
+    fn decode(path: &str, content: &[u8]) -> Option<(String, &'static str)> {
+        let repository_path = scratch_repository("decode-blob");
+        let inner = git2::Repository::open(&repository_path).unwrap();
+        let attributes = inner
+            .blob(b"*.txt working-tree-encoding=ISO-8859-2\n")
+            .unwrap();
+        let mut builder = inner.treebuilder(None).unwrap();
+        builder
+            .insert(".gitattributes", attributes, 0o100644)
+            .unwrap();
+        let tree = inner.find_tree(builder.write().unwrap()).unwrap();
+        let theme = syntect::highlighting::Theme::default();
+        let decoded = templator(&repository_path, &theme)
+            .decode_blob(&tree, std::path::Path::new(path), content)
+            .map(|(text, encoding)| (text.into_owned(), encoding.name()));
+        std::fs::remove_dir_all(&repository_path).unwrap();
+        decoded
+    }
+
+    #[test]
+    fn utf8_is_taken_as_is() {
+        assert_eq!(
+            decode("a.txt", "zażółć".as_bytes()),
+            Some(("zażółć".to_string(), "UTF-8"))
+        );
+    }
+
+    #[test]
+    fn declared_encodings_are_used() {
+        assert_eq!(
+            decode("a.txt", b"za\xbf\xf3\xb3\xe6"),
+            Some(("zażółć".to_string(), "ISO-8859-2"))
+        );
+    }
+
+    #[test]
+    fn byte_order_marks_are_recognized() {
+        assert_eq!(
+            decode("a.rs", b"\xff\xfeh\0i\0"),
+            Some(("hi".to_string(), "UTF-16LE"))
+        );
+    }
+
+    #[test]
+    fn binary_content_is_not_decoded() {
+        assert_eq!(decode("a.rs", b"\xffbinary\0data"), None);
+        assert_eq!(decode("a.rs", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\xff"), None);
+    }
+}